@@ -0,0 +1,174 @@
+use std::alloc::{self, Layout};
+use std::fmt;
+use std::ptr::NonNull;
+
+/// Immutable flat collection whose content buffer is over-aligned, e.g.
+/// on 32 or 64 bytes so that SIMD kernels can use aligned loads. The
+/// start of every sub-array can optionally be padded to the same
+/// alignment, the padding being filled with `T::default()`.
+pub struct AlignedFlatArray<T: Copy> {
+    ptr: NonNull<T>,
+    layout: Layout,
+    /// Number of elements of the buffer, padding included.
+    len: usize,
+    /// Start of every sub-array in the buffer.
+    starts: Box<[usize]>,
+    /// End of every sub-array in the buffer.
+    ends: Box<[usize]>,
+}
+
+// SAFETY: the buffer is owned by the collection, like the one of a
+// `Box<[T]>`.
+unsafe impl<T: Copy + Send> Send for AlignedFlatArray<T> {}
+unsafe impl<T: Copy + Sync> Sync for AlignedFlatArray<T> {}
+
+impl<T: Copy + Default> AlignedFlatArray<T> {
+    /// Copies the sub-arrays into a buffer aligned on `align` bytes. If
+    /// `pad_arrays` is `true`, every sub-array also starts on an
+    /// `align`-byte boundary. Returns `None` if `align` is not a power of
+    /// two at least as large as the alignment of `T`, or, when padding,
+    /// if it is not a multiple of the size of `T`.
+    pub fn new<I, A>(arrays: I, align: usize, pad_arrays: bool) -> Option<Self>
+    where
+        I: IntoIterator<Item = A>,
+        A: AsRef<[T]>,
+    {
+        if !align.is_power_of_two() || align < align_of::<T>() {
+            return None;
+        }
+        let step = match pad_arrays {
+            true if size_of::<T>() == 0 || !align.is_multiple_of(size_of::<T>()) => return None,
+            true => align / size_of::<T>(),
+            false => 1,
+        };
+        let arrays: Vec<A> = arrays.into_iter().collect();
+        let mut starts = Vec::with_capacity(arrays.len());
+        let mut ends = Vec::with_capacity(arrays.len());
+        let mut len: usize = 0;
+        for array in &arrays {
+            let start = len.next_multiple_of(step);
+            len = start + array.as_ref().len();
+            starts.push(start);
+            ends.push(len);
+        }
+        let layout = Layout::from_size_align(len.checked_mul(size_of::<T>())?, align).ok()?;
+        let ptr = if layout.size() == 0 {
+            NonNull::new(std::ptr::without_provenance_mut(align))?
+        } else {
+            // SAFETY: the layout has a non-zero size.
+            let raw = unsafe { alloc::alloc(layout) } as *mut T;
+            NonNull::new(raw).unwrap_or_else(|| alloc::handle_alloc_error(layout))
+        };
+        let mut written = 0;
+        for (array, &start) in arrays.iter().zip(&starts) {
+            let array = array.as_ref();
+            // SAFETY: `written..start + array.len()` is inside the buffer of
+            // `len` elements, and `array` cannot overlap the new buffer.
+            unsafe {
+                for i in written..start {
+                    ptr.add(i).write(T::default());
+                }
+                ptr.add(start)
+                    .copy_from_nonoverlapping(NonNull::from(array).cast(), array.len());
+            }
+            written = start + array.len();
+        }
+        Some(Self {
+            ptr,
+            layout,
+            len,
+            starts: starts.into_boxed_slice(),
+            ends: ends.into_boxed_slice(),
+        })
+    }
+}
+
+impl<T: Copy> AlignedFlatArray<T> {
+    /// Alignment of the buffer, in bytes.
+    pub fn align(&self) -> usize {
+        self.layout.align()
+    }
+    /// Number of sub-arrays.
+    pub fn num_arrays(&self) -> usize {
+        self.starts.len()
+    }
+    /// Borrows the whole buffer, padding included.
+    pub fn get_content(&self) -> &[T] {
+        // SAFETY: the `len` elements of the buffer are initialized.
+        unsafe { std::slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+    }
+    /// Mutably borrows the whole buffer, padding included.
+    pub fn get_mut_content(&mut self) -> &mut [T] {
+        // SAFETY: the `len` elements of the buffer are initialized.
+        unsafe { std::slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+    }
+    /// Returns the i-th sub-array, or `None` if `i` is out of bounds.
+    pub fn get(&self, i: usize) -> Option<&[T]> {
+        Some(&self.get_content()[*self.starts.get(i)?..self.ends[i]])
+    }
+    /// Mutably borrows the i-th sub-array, or returns `None` if `i` is
+    /// out of bounds.
+    pub fn get_mut(&mut self, i: usize) -> Option<&mut [T]> {
+        let range = *self.starts.get(i)?..self.ends[i];
+        Some(&mut self.get_mut_content()[range])
+    }
+    /// Returns an iterator over the sub-arrays.
+    pub fn iter_arrays(&self) -> impl Iterator<Item = &[T]> {
+        let content = self.get_content();
+        self.starts
+            .iter()
+            .zip(self.ends.iter())
+            .map(move |(&start, &end)| &content[start..end])
+    }
+}
+
+impl<T: Copy> Drop for AlignedFlatArray<T> {
+    fn drop(&mut self) {
+        if self.layout.size() != 0 {
+            // SAFETY: the buffer was allocated with this layout.
+            unsafe { alloc::dealloc(self.ptr.as_ptr() as *mut u8, self.layout) }
+        }
+    }
+}
+
+impl<T: Copy + fmt::Debug> fmt::Debug for AlignedFlatArray<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter_arrays()).finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_aligned_content() {
+        let arrays = vec![vec![1.0f32, 2.0, 3.0], vec![], vec![4.0; 20]];
+        let aligned = AlignedFlatArray::new(&arrays, 64, false).unwrap();
+        assert_eq!(0, aligned.get_content().as_ptr() as usize % 64);
+        assert_eq!(23, aligned.get_content().len());
+        assert!(
+            aligned
+                .iter_arrays()
+                .eq(arrays.iter().map(|a| a.as_slice()))
+        );
+    }
+
+    #[test]
+    fn test_aligned_arrays() {
+        let arrays = vec![vec![1u16; 5], vec![2; 40], vec![3; 1]];
+        let mut aligned = AlignedFlatArray::new(&arrays, 32, true).unwrap();
+        for (array, expected) in aligned.iter_arrays().zip(&arrays) {
+            assert_eq!(0, array.as_ptr() as usize % 32);
+            assert_eq!(expected.as_slice(), array);
+        }
+        // 5 elements padded to 16, 40 padded to 48, then 1.
+        assert_eq!(16 + 48 + 1, aligned.get_content().len());
+        assert_eq!(0, aligned.get_content()[5]);
+        aligned.get_mut(2).unwrap()[0] = 7;
+        assert_eq!(Some(&[7][..]), aligned.get(2));
+        assert_eq!(None, aligned.get(3));
+        assert!(AlignedFlatArray::new([[1u64]], 4, false).is_none());
+        assert!(AlignedFlatArray::<[u8; 3]>::new([[[0u8; 3]]], 64, true).is_none());
+    }
+}
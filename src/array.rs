@@ -1,29 +1,73 @@
-use crate::iterator::{FlattenedCollection, Iter, IterMut};
-use serde::{Deserialize, Serialize};
+use crate::error::FlatError;
+use crate::iterator::{
+    FlattenedCollection, FlattenedCollectionMut, Iter, IterMut, get_offset, get_range,
+    get_range_mut,
+};
+use crate::offset::{Offset, convert_offsets, flatten_nested};
+use crate::vector::FlatVec;
+#[cfg(feature = "serde")]
+use serde::Serialize;
 use std::borrow::Cow;
 use std::ops::Deref;
 
 /// Custom datastructure built for reducing cache misses. This is a unmutable
 /// datastructure
-#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 #[derive(Debug, Eq, PartialEq, PartialOrd, Ord, Hash, Clone)]
-pub struct FlatArray<T> {
+pub struct FlatArray<T, O = usize> {
     pub(crate) content: Box<[T]>,
-    pub(crate) indices: Box<[usize]>,
+    pub(crate) indices: Box<[O]>,
 }
 
-impl<T> FlattenedCollection<T> for FlatArray<T> {
+impl<T, O: Offset> FlattenedCollection<T> for FlatArray<T, O> {
     fn indices_len(&self) -> usize {
         self.indices.len()
     }
+    fn content_len(&self) -> usize {
+        self.content.len()
+    }
     unsafe fn get_indices(&self, index: usize) -> usize {
-        unsafe { *self.indices.get_unchecked(index) }
+        unsafe { get_offset(&self.indices, index) }
     }
     unsafe fn get_content(&self, range: std::ops::Range<usize>) -> &[T] {
-        unsafe { self.content.get_unchecked(range) }
+        unsafe { get_range(&self.content, range) }
     }
+}
+
+impl<T, O: Offset> FlattenedCollectionMut<T> for FlatArray<T, O> {
     unsafe fn get_mut_content(&mut self, range: std::ops::Range<usize>) -> &mut [T] {
-        unsafe { self.content.get_unchecked_mut(range) }
+        unsafe { get_range_mut(&mut self.content, range) }
+    }
+}
+
+impl<T, O: Offset> FlatArray<T, O> {
+    /// Builds the `FlatArray` from nested vectors, storing the offsets as
+    /// `O`. Fails with `FlatError::OffsetOverflow` if an offset does not
+    /// fit in `O`.
+    pub fn try_from_nested(vecs: Vec<Vec<T>>) -> Result<Self, FlatError> {
+        let (content, indices) = flatten_nested(vecs)?;
+        Ok(Self {
+            content: content.into_boxed_slice(),
+            indices: indices.into_boxed_slice(),
+        })
+    }
+    /// Converts the offsets back to `usize`.
+    pub fn into_usize_offsets(self) -> FlatArray<T> {
+        FlatArray {
+            content: self.content,
+            indices: self.indices.iter().map(|o| o.to_usize()).collect(),
+        }
+    }
+}
+
+impl<T> FlatArray<T> {
+    /// Converts the offsets to a smaller type `O`, e.g. `u32`. Fails with
+    /// `FlatError::OffsetOverflow` if an offset does not fit in `O`.
+    pub fn try_into_offsets<O: Offset>(self) -> Result<FlatArray<T, O>, FlatError> {
+        Ok(FlatArray {
+            indices: convert_offsets(&self.indices)?.into_boxed_slice(),
+            content: self.content,
+        })
     }
 }
 
@@ -47,6 +91,19 @@ impl<T> FlatArray<T> {
     }
 }
 
+impl<T: Send> FlatArray<T> {
+    /// Build the `FlatArray` from a `Vec<Vec<T>>`, copying the
+    /// sub-arrays on several threads. See
+    /// `FlatVec::from_nested_parallel`.
+    pub fn from_nested_parallel(value: Vec<Vec<T>>) -> Self {
+        let flat_vec = FlatVec::from_nested_parallel(value);
+        Self {
+            content: flat_vec.content.into_boxed_slice(),
+            indices: flat_vec.indices.into_boxed_slice(),
+        }
+    }
+}
+
 impl<T> FlatArray<T> {
     /// Borrows the content of the FlatArray as a slice.
     pub fn get_content(&self) -> &[T] {
@@ -54,7 +111,7 @@ impl<T> FlatArray<T> {
     }
 }
 
-impl<'a, T> FlatArray<T> {
+impl<'a, T, O: Offset> FlatArray<T, O> {
     /// Returns an iterator over the content. This iterator returns the
     /// individual elements.
     pub fn iter(&'a self) -> std::slice::Iter<'a, T> {
@@ -62,13 +119,13 @@ impl<'a, T> FlatArray<T> {
     }
     /// Returns an iterator over the arrays/vectors used to build the
     /// `FlatArray`. The iterator will return a slice of type `&[T]`.
-    pub fn iter_arrays(&'a self) -> Iter<'a, FlatArray<T>, T> {
+    pub fn iter_arrays(&'a self) -> Iter<'a, FlatArray<T, O>, T> {
         Iter::new(self)
     }
     /// Returns an iterator over the arrays/vectors used to build the
     /// `FlatArray`. The iterator will return a slice of type `&mut [T]`.
-    pub fn iter_arrays_mut(&'a mut self) -> IterMut<'a, FlatArray<T>, T> {
-        IterMut::new(self)
+    pub fn iter_arrays_mut(&'a mut self) -> IterMut<'a, FlatArray<T, O>, T, O> {
+        IterMut::new(&self.indices, &mut self.content)
     }
 }
 
@@ -108,6 +165,59 @@ where
     }
 }
 
+impl<T> FlatArray<T> {
+    /// Number of heap bytes allocated for the content and the offsets.
+    /// Heap memory owned by the elements themselves (e.g. the buffers of
+    /// `String`s) is not counted.
+    pub fn mem_usage(&self) -> usize {
+        size_of_val(&*self.content) + size_of_val(&*self.indices)
+    }
+    /// Consumes the `FlatArray`, returning its content without copying.
+    pub fn into_content(self) -> Box<[T]> {
+        self.content
+    }
+    /// Consumes the `FlatArray`, returning its offsets without copying.
+    pub fn into_indices(self) -> Box<[usize]> {
+        self.indices
+    }
+    /// Consumes the `FlatArray`, returning its content and its offsets.
+    pub fn into_parts(self) -> (Box<[T]>, Box<[usize]>) {
+        (self.content, self.indices)
+    }
+    /// Converts the `FlatArray` into a `FlatVec`, without copying the
+    /// buffers.
+    pub fn into_flatvec(self) -> FlatVec<T> {
+        FlatVec {
+            content: self.content.into_vec(),
+            indices: self.indices.into_vec(),
+        }
+    }
+}
+
+impl<T> From<FlatVec<T>> for FlatArray<T> {
+    fn from(value: FlatVec<T>) -> Self {
+        value.into_flatarray()
+    }
+}
+
+impl<T> From<FlatArray<T>> for FlatVec<T> {
+    fn from(value: FlatArray<T>) -> Self {
+        value.into_flatvec()
+    }
+}
+
+impl<T: Clone> From<&[&[T]]> for FlatArray<T> {
+    fn from(value: &[&[T]]) -> Self {
+        FlatVec::from(value).into_flatarray()
+    }
+}
+
+impl<T: Clone> From<Vec<&[T]>> for FlatArray<T> {
+    fn from(value: Vec<&[T]>) -> Self {
+        FlatVec::from(value).into_flatarray()
+    }
+}
+
 impl<T> From<Vec<Vec<T>>> for FlatArray<T> {
     #[inline(always)]
     fn from(value: Vec<Vec<T>>) -> Self {
@@ -237,4 +347,65 @@ mod test {
         };
         assert_eq!(expected, actual);
     }
+
+    #[test]
+    fn test_iter_arrays_mut() {
+        let mut flat = FlatArray::new(vec![vec![1, 2, 3], vec![], vec![4, 5]]);
+        let arrays: Vec<&mut [i32]> = flat.iter_arrays_mut().collect();
+        assert_eq!(arrays.len(), 3);
+        for array in arrays {
+            array.iter_mut().for_each(|x| *x *= 10);
+        }
+        let expected = FlatArray::new(vec![vec![10, 20, 30], vec![], vec![40, 50]]);
+        assert_eq!(expected, flat);
+    }
+
+    #[test]
+    fn test_flatvec_conversions() {
+        let flat_vec = FlatVec::new(vec![vec![1, 2], vec![], vec![3]]);
+        let flat_array = flat_vec.clone().into_flatarray();
+        assert_eq!(&[0, 2, 2, 3][..], &*flat_array.indices);
+        let back: FlatVec<i32> = flat_array.into();
+        assert_eq!(flat_vec, back);
+        assert_eq!(FlatArray::from(back.clone()).into_flatvec(), back);
+    }
+
+    #[test]
+    fn test_into_parts() {
+        let flat_array = FlatArray::new(vec![vec![1, 2], vec![3]]);
+        assert_eq!(&[1, 2, 3][..], &*flat_array.clone().into_content());
+        assert_eq!(&[0, 2, 3][..], &*flat_array.clone().into_indices());
+        let (content, indices) = flat_array.into_parts();
+        assert_eq!((3, 3), (content.len(), indices.len()));
+    }
+
+    #[test]
+    fn test_from_slices() {
+        let slices: Vec<&[u32]> = vec![&[1, 2], &[], &[3]];
+        let flat_array = FlatArray::from(slices.as_slice());
+        assert_eq!(&[0, 2, 2, 3][..], &*flat_array.indices);
+        assert_eq!(flat_array, FlatArray::from(slices));
+    }
+
+    #[test]
+    fn test_mem_usage() {
+        let flat_array = FlatArray::new(vec![vec![1u16, 2], vec![3]]);
+        assert_eq!(3 * 2 + 3 * size_of::<usize>(), flat_array.mem_usage());
+    }
+
+    #[test]
+    fn test_offset_type() {
+        let vecs = vec![vec![1u8, 2], vec![], vec![3]];
+        let flat_array = FlatArray::<u8, u32>::try_from_nested(vecs.clone()).unwrap();
+        assert_eq!(&[0u32, 2, 2, 3][..], &*flat_array.indices);
+        assert_eq!(
+            vec![&[1, 2][..], &[], &[3]],
+            flat_array.iter_arrays().collect::<Vec<_>>()
+        );
+        assert_eq!(FlatArray::new(vecs), flat_array.into_usize_offsets());
+        assert_eq!(
+            Err(FlatError::OffsetOverflow),
+            FlatArray::<u8, u16>::try_from_nested(vec![vec![0; 70_000]])
+        );
+    }
 }
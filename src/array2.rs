@@ -0,0 +1,179 @@
+use crate::FlatView;
+use crate::iterator::{
+    FlattenedCollection, FlattenedCollectionMut, Iter, get_offset, get_range, get_range_mut,
+};
+use std::ops::{Deref, Range};
+
+/// Two-level flat collection, mimicking a `Vec<Vec<Vec<T>>>` (e.g.
+/// documents made of sentences made of tokens). The content is stored
+/// in a single buffer, split into inner arrays by `inner_indices`, and
+/// the inner arrays are themselves grouped into outer arrays by
+/// `outer_indices`.
+#[derive(Debug, Eq, PartialEq, PartialOrd, Ord, Hash, Clone)]
+pub struct FlatArray2<T> {
+    pub(crate) content: Box<[T]>,
+    /// Offsets of the inner arrays in `content`.
+    pub(crate) inner_indices: Box<[usize]>,
+    /// Offsets of the outer arrays in `inner_indices`.
+    pub(crate) outer_indices: Box<[usize]>,
+}
+
+impl<T> FlattenedCollection<T> for FlatArray2<T> {
+    fn indices_len(&self) -> usize {
+        self.inner_indices.len()
+    }
+    fn content_len(&self) -> usize {
+        self.content.len()
+    }
+    unsafe fn get_indices(&self, index: usize) -> usize {
+        unsafe { get_offset(&self.inner_indices, index) }
+    }
+    unsafe fn get_content(&self, range: Range<usize>) -> &[T] {
+        unsafe { get_range(&self.content, range) }
+    }
+}
+
+impl<T> FlattenedCollectionMut<T> for FlatArray2<T> {
+    unsafe fn get_mut_content(&mut self, range: Range<usize>) -> &mut [T] {
+        unsafe { get_range_mut(&mut self.content, range) }
+    }
+}
+
+impl<T> Default for FlatArray2<T> {
+    fn default() -> Self {
+        Self {
+            content: Box::new([]),
+            inner_indices: Box::new([0]),
+            outer_indices: Box::new([0]),
+        }
+    }
+}
+
+impl<T> FlatArray2<T> {
+    pub fn new(vecs: Vec<Vec<Vec<T>>>) -> Self {
+        Self::from(vecs)
+    }
+    /// Number of outer arrays.
+    pub fn num_outer(&self) -> usize {
+        self.outer_indices.len() - 1
+    }
+    /// Number of inner arrays, across all the outer arrays.
+    pub fn num_inner(&self) -> usize {
+        self.inner_indices.len() - 1
+    }
+    /// Borrows the content of the FlatArray2 as a slice.
+    pub fn get_content(&self) -> &[T] {
+        self.content.as_ref()
+    }
+}
+
+impl<'a, T> FlatArray2<T> {
+    /// Returns an iterator over the content. This iterator returns the
+    /// individual elements.
+    pub fn iter(&'a self) -> std::slice::Iter<'a, T> {
+        self.content.iter()
+    }
+    /// Returns an iterator over the outer arrays. Each outer array is a
+    /// `FlatView` over its inner arrays.
+    pub fn iter_outer(&'a self) -> OuterIter<'a, T> {
+        OuterIter {
+            content: &self.content,
+            inner_indices: &self.inner_indices,
+            outer_indices: self.outer_indices.windows(2),
+        }
+    }
+    /// Returns an iterator over all the inner arrays, regardless of the
+    /// outer array they belong to. The iterator will return a slice of
+    /// type `&[T]`.
+    pub fn iter_inner(&'a self) -> Iter<'a, FlatArray2<T>, T> {
+        Iter::new(self)
+    }
+}
+
+impl<T> Deref for FlatArray2<T> {
+    type Target = [T];
+    fn deref(&self) -> &Self::Target {
+        self.content.as_ref()
+    }
+}
+
+impl<T> From<Vec<Vec<Vec<T>>>> for FlatArray2<T> {
+    fn from(value: Vec<Vec<Vec<T>>>) -> Self {
+        let inner_length: usize = value.iter().map(|v| v.len()).sum();
+        let length: usize = value.iter().flatten().map(|v| v.len()).sum();
+        let mut content = Vec::with_capacity(length);
+        let mut inner_indices = Vec::with_capacity(inner_length + 1);
+        let mut outer_indices = Vec::with_capacity(value.len() + 1);
+        inner_indices.push(0);
+        outer_indices.push(0);
+        for outer in value {
+            outer_indices.push(outer_indices.last().unwrap() + outer.len());
+            for inner in outer {
+                inner_indices.push(inner_indices.last().unwrap() + inner.len());
+                content.extend(inner);
+            }
+        }
+        Self {
+            content: content.into_boxed_slice(),
+            inner_indices: inner_indices.into_boxed_slice(),
+            outer_indices: outer_indices.into_boxed_slice(),
+        }
+    }
+}
+
+/// Iterator over the outer arrays of a `FlatArray2`, yielding a
+/// `FlatView` per outer array.
+#[derive(Debug, Clone)]
+pub struct OuterIter<'a, T> {
+    content: &'a [T],
+    inner_indices: &'a [usize],
+    outer_indices: std::slice::Windows<'a, usize>,
+}
+
+impl<'a, T> Iterator for OuterIter<'a, T> {
+    type Item = FlatView<'a, T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let bounds = self.outer_indices.next()?;
+        Some(FlatView {
+            content: self.content,
+            indices: &self.inner_indices[bounds[0]..=bounds[1]],
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn build_documents() -> Vec<Vec<Vec<&'static str>>> {
+        vec![
+            vec![vec!["Hello", "world"], vec!["Bye"]],
+            vec![],
+            vec![vec!["Hi"]],
+        ]
+    }
+
+    #[test]
+    fn test_iter_outer() {
+        let documents = build_documents();
+        let flat = FlatArray2::new(documents.clone());
+        assert_eq!(3, flat.num_outer());
+        assert_eq!(3, flat.num_inner());
+        let actual: Vec<Vec<Vec<&str>>> = flat
+            .iter_outer()
+            .map(|doc| doc.iter_arrays().map(|s| s.to_vec()).collect())
+            .collect();
+        assert_eq!(documents, actual);
+        let last = flat.iter_outer().last().unwrap();
+        assert_eq!(&["Hi"], &last[..]);
+    }
+
+    #[test]
+    fn test_iter_inner() {
+        let flat = FlatArray2::new(build_documents());
+        let actual: Vec<_> = flat.iter_inner().collect();
+        let expected: Vec<&[&str]> = vec![&["Hello", "world"], &["Bye"], &["Hi"]];
+        assert_eq!(expected, actual);
+    }
+}
@@ -0,0 +1,226 @@
+//! Stable, versioned binary encoding of the flat collections, readable
+//! across versions of the crate. All integers are little-endian:
+//!
+//! | bytes        | content                                          |
+//! |--------------|--------------------------------------------------|
+//! | 4            | magic bytes `FLAT`                               |
+//! | 2            | format version (`u16`), currently `1`            |
+//! | 1            | element tag (`Element::TAG`)                     |
+//! | 1            | element size in bytes (`Element::SIZE`)          |
+//! | 8            | number of offsets `n` (`u64`)                    |
+//! | 8            | number of elements `m` (`u64`)                   |
+//! | 8 * n        | offsets (`u64`)                                  |
+//! | SIZE * m     | elements                                         |
+use crate::iterator::indices_are_valid;
+use crate::{FlatArray, FlatVec};
+use std::io::{self, Read, Write};
+
+/// Magic bytes starting every encoded collection.
+pub const MAGIC: [u8; 4] = *b"FLAT";
+/// Version of the format written by this crate.
+pub const VERSION: u16 = 1;
+
+/// Number of bytes encoded before being handed to the writer.
+const CHUNK_SIZE: usize = 8192;
+
+/// Fixed-size element that can be stored in the binary format.
+pub trait Element: Sized {
+    /// Identifies the element type in the header, so that a collection
+    /// is not read back as a different type.
+    const TAG: u8;
+    /// Number of bytes of an encoded element.
+    const SIZE: usize;
+    /// Writes the element in `SIZE` little-endian bytes.
+    fn write_le(&self, bytes: &mut [u8]);
+    /// Reads an element from `SIZE` little-endian bytes.
+    fn read_le(bytes: &[u8]) -> Self;
+}
+
+macro_rules! impl_element {
+    ($($t:ty => $tag:expr),* $(,)?) => {
+        $(
+            impl Element for $t {
+                const TAG: u8 = $tag;
+                const SIZE: usize = size_of::<$t>();
+                fn write_le(&self, bytes: &mut [u8]) {
+                    bytes.copy_from_slice(&self.to_le_bytes());
+                }
+                fn read_le(bytes: &[u8]) -> Self {
+                    <$t>::from_le_bytes(bytes.try_into().unwrap())
+                }
+            }
+        )*
+    };
+}
+
+impl_element!(
+    u8 => 1, u16 => 2, u32 => 3, u64 => 4,
+    i8 => 5, i16 => 6, i32 => 7, i64 => 8,
+    f32 => 9, f64 => 10,
+);
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Writes the elements of `values`, `CHUNK_SIZE` bytes at a time.
+pub(crate) fn write_elements<T: Element>(writer: &mut impl Write, values: &[T]) -> io::Result<()> {
+    let per_chunk = (CHUNK_SIZE / T::SIZE).max(1);
+    let mut buffer = vec![0; per_chunk * T::SIZE];
+    for chunk in values.chunks(per_chunk) {
+        for (value, bytes) in chunk.iter().zip(buffer.chunks_exact_mut(T::SIZE)) {
+            value.write_le(bytes);
+        }
+        writer.write_all(&buffer[..chunk.len() * T::SIZE])?;
+    }
+    Ok(())
+}
+
+/// Reads `len` elements, `CHUNK_SIZE` bytes at a time.
+pub(crate) fn read_elements<T: Element>(reader: &mut impl Read, len: usize) -> io::Result<Vec<T>> {
+    let per_chunk = (CHUNK_SIZE / T::SIZE).max(1);
+    let mut buffer = vec![0; per_chunk * T::SIZE];
+    // The length comes from the input: do not trust it for allocating.
+    let mut values = Vec::with_capacity(len.min(per_chunk));
+    let mut remaining = len;
+    while remaining > 0 {
+        let count = remaining.min(per_chunk);
+        let bytes = &mut buffer[..count * T::SIZE];
+        reader.read_exact(bytes)?;
+        values.extend(bytes.chunks_exact(T::SIZE).map(T::read_le));
+        remaining -= count;
+    }
+    Ok(values)
+}
+
+pub(crate) fn to_u64(offsets: &[usize]) -> Vec<u64> {
+    offsets.iter().map(|&o| o as u64).collect()
+}
+
+/// Writes the header of a collection with `indices_len` offsets and
+/// `content_len` elements.
+pub(crate) fn write_header<T: Element>(
+    writer: &mut impl Write,
+    indices_len: usize,
+    content_len: usize,
+) -> io::Result<()> {
+    writer.write_all(&MAGIC)?;
+    writer.write_all(&VERSION.to_le_bytes())?;
+    writer.write_all(&[T::TAG, T::SIZE as u8])?;
+    writer.write_all(&(indices_len as u64).to_le_bytes())?;
+    writer.write_all(&(content_len as u64).to_le_bytes())
+}
+
+/// Writes a whole collection, header included.
+pub(crate) fn write_collection<T: Element>(
+    writer: &mut impl Write,
+    content: &[T],
+    indices: &[usize],
+) -> io::Result<()> {
+    write_header::<T>(writer, indices.len(), content.len())?;
+    write_elements(writer, &to_u64(indices))?;
+    write_elements(writer, content)
+}
+
+/// Reads and checks a header, returning the number of offsets and of
+/// elements.
+pub(crate) fn read_header<T: Element>(reader: &mut impl Read) -> io::Result<(usize, usize)> {
+    let mut header = [0; 24];
+    reader.read_exact(&mut header)?;
+    if header[..4] != MAGIC {
+        return Err(invalid_data("not a flat collection: wrong magic bytes"));
+    }
+    if u16::from_le_bytes([header[4], header[5]]) != VERSION {
+        return Err(invalid_data("unsupported format version"));
+    }
+    if header[6] != T::TAG || usize::from(header[7]) != T::SIZE {
+        return Err(invalid_data("the collection holds another element type"));
+    }
+    let to_usize = |bytes: &[u8]| {
+        usize::try_from(u64::from_le_bytes(bytes.try_into().unwrap()))
+            .map_err(|_| invalid_data("length does not fit in memory"))
+    };
+    Ok((to_usize(&header[8..16])?, to_usize(&header[16..24])?))
+}
+
+/// Reads a whole collection, header included, and checks its offsets.
+pub(crate) fn read_collection<T: Element>(
+    reader: &mut impl Read,
+) -> io::Result<(Vec<T>, Vec<usize>)> {
+    let (indices_len, content_len) = read_header::<T>(reader)?;
+    let indices = read_elements::<u64>(reader, indices_len)?
+        .into_iter()
+        .map(|o| usize::try_from(o).map_err(|_| invalid_data("offset does not fit in memory")))
+        .collect::<io::Result<Vec<usize>>>()?;
+    let content = read_elements(reader, content_len)?;
+    if !indices_are_valid(&indices, content.len()) {
+        return Err(invalid_data(
+            "offsets are decreasing or past the end of the content",
+        ));
+    }
+    Ok((content, indices))
+}
+
+impl<T: Element> FlatVec<T> {
+    /// Writes the `FlatVec` in the crate's binary format. See the
+    /// `binary` module.
+    pub fn to_writer(&self, mut writer: impl Write) -> io::Result<()> {
+        write_collection(&mut writer, &self.content, &self.indices)
+    }
+    /// Reads a `FlatVec` written in the crate's binary format. Fails
+    /// with `io::ErrorKind::InvalidData` if the input is not a valid
+    /// collection of `T`.
+    pub fn from_reader(mut reader: impl Read) -> io::Result<Self> {
+        let (content, indices) = read_collection(&mut reader)?;
+        Ok(Self { content, indices })
+    }
+}
+
+impl<T: Element> FlatArray<T> {
+    /// Writes the `FlatArray` in the crate's binary format. See the
+    /// `binary` module.
+    pub fn to_writer(&self, mut writer: impl Write) -> io::Result<()> {
+        write_collection(&mut writer, &self.content, &self.indices)
+    }
+    /// Reads a `FlatArray` written in the crate's binary format. Fails
+    /// with `io::ErrorKind::InvalidData` if the input is not a valid
+    /// collection of `T`.
+    pub fn from_reader(mut reader: impl Read) -> io::Result<Self> {
+        let (content, indices) = read_collection(&mut reader)?;
+        Ok(Self {
+            content: content.into_boxed_slice(),
+            indices: indices.into_boxed_slice(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_binary_roundtrip() {
+        let flat = FlatVec::new(vec![vec![1.5f32, 2.0], vec![], vec![-3.0]]);
+        let mut bytes = Vec::new();
+        flat.to_writer(&mut bytes).unwrap();
+        assert_eq!(&MAGIC, &bytes[..4]);
+        assert_eq!(24 + 4 * 8 + 3 * 4, bytes.len());
+        assert_eq!(flat, FlatVec::from_reader(&bytes[..]).unwrap());
+        let flat_array = FlatArray::from_reader(&bytes[..]).unwrap();
+        assert_eq!(FlatArray::from(flat), flat_array);
+    }
+
+    #[test]
+    fn test_binary_invalid_input() {
+        let mut bytes = Vec::new();
+        FlatVec::new(vec![vec![1u32, 2]])
+            .to_writer(&mut bytes)
+            .unwrap();
+        let error = FlatVec::<u64>::from_reader(&bytes[..]).unwrap_err();
+        assert_eq!(io::ErrorKind::InvalidData, error.kind());
+        bytes[24] = 9;
+        let error = FlatVec::<u32>::from_reader(&bytes[..]).unwrap_err();
+        assert_eq!(io::ErrorKind::InvalidData, error.kind());
+        assert!(FlatVec::<u32>::from_reader(&bytes[..10]).is_err());
+    }
+}
@@ -1,20 +1,33 @@
 use std::mem::take;
 
-use serde::{Deserialize, Serialize};
+#[cfg(feature = "serde")]
+use serde::Serialize;
 
 use crate::FlatArray;
 use crate::FlatStr;
 use crate::FlatVec;
+use crate::error::FlatError;
 
-#[derive(Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[derive(Debug)]
 /// This struct can be used to dynamically build a `FlatArray` or a FlatVec by pushing
 /// element into it.
 pub struct FlatBuilder<T> {
-    content: Vec<T>,
-    indices: Vec<usize>,
+    pub(crate) content: Vec<T>,
+    pub(crate) indices: Vec<usize>,
 }
 
 impl<T> FlatBuilder<T> {
+    /// Creates an empty builder with room for `content_capacity` elements
+    /// spread over `arrays_capacity` sub-arrays.
+    pub fn with_capacity(content_capacity: usize, arrays_capacity: usize) -> Self {
+        let mut indices = Vec::with_capacity(arrays_capacity + 1);
+        indices.push(0);
+        Self {
+            content: Vec::with_capacity(content_capacity),
+            indices,
+        }
+    }
     pub fn push_exact_sized<I: IntoIterator<Item = T> + ExactSizeIterator>(&mut self, item: I) {
         unsafe {
             self.indices
@@ -24,6 +37,69 @@ impl<T> FlatBuilder<T> {
             self.content.push(s);
         }
     }
+    /// Checked version of `push_exact_sized`: fails with
+    /// `FlatError::LengthMismatch` if `item` does not yield as many
+    /// elements as its reported length. On error, the builder is left
+    /// unchanged.
+    pub fn try_push_exact_sized<I>(&mut self, item: I) -> Result<(), FlatError>
+    where
+        I: IntoIterator<Item = T> + ExactSizeIterator,
+    {
+        let expected = item.len();
+        let start = self.content.len();
+        self.content.reserve(expected);
+        self.content.extend(item);
+        let actual = self.content.len() - start;
+        if actual != expected {
+            self.content.truncate(start);
+            return Err(FlatError::LengthMismatch { expected, actual });
+        }
+        self.push_offset(start, actual)
+    }
+    /// Checked version of `push`: fails with `FlatError::OffsetOverflow`
+    /// instead of wrapping around if the new offset overflows. On error,
+    /// the builder is left unchanged.
+    pub fn try_push<I: IntoIterator<Item = T>>(&mut self, item: I) -> Result<(), FlatError> {
+        let start = self.content.len();
+        self.content.extend(item);
+        let count = self.content.len() - start;
+        self.push_offset(start, count)
+    }
+    /// Pushes the `Ok` elements of `item` as a new sub-array, stopping at
+    /// the first error. On error, the partially pushed sub-array is
+    /// removed and the error is returned.
+    pub fn try_push_fallible<I, E>(&mut self, item: I) -> Result<(), E>
+    where
+        I: IntoIterator<Item = Result<T, E>>,
+    {
+        let start = self.content.len();
+        for element in item {
+            match element {
+                Ok(element) => self.content.push(element),
+                Err(e) => {
+                    self.content.truncate(start);
+                    return Err(e);
+                }
+            }
+        }
+        self.indices.push(self.content.len());
+        Ok(())
+    }
+    /// Pushes the offset of a sub-array of `count` elements, truncating
+    /// the content back to `start` if it overflows.
+    fn push_offset(&mut self, start: usize, count: usize) -> Result<(), FlatError> {
+        let last = *self.indices.last().ok_or(FlatError::InvalidIndices)?;
+        match last.checked_add(count) {
+            Some(offset) => {
+                self.indices.push(offset);
+                Ok(())
+            }
+            None => {
+                self.content.truncate(start);
+                Err(FlatError::OffsetOverflow)
+            }
+        }
+    }
     pub fn push<I: IntoIterator<Item = T>>(&mut self, item: I) {
         let mut current_indice = unsafe { *self.indices.last().unwrap_unchecked() };
         for s in item {
@@ -45,18 +121,65 @@ impl<T> FlatBuilder<T> {
         }
         self.indices.push(current_indice)
     }
-    // pub fn push_str<I, S>(&mut self, item: I)
-    // where
-    //     I: IntoIterator<Item = S>,
-    //     S: Deref<Target = str>,
-    // {
-    //     let mut current_indice = unsafe { *self.indices.last().unwrap_unchecked() };
-    //     for s in item {
-    //         self.content.push(s.deref().as_bytes());
-    //         current_indice += 1;
-    //     }
-    //     self.indices.push(current_indice)
-    // }
+    /// Number of elements pushed so far, across all the sub-arrays.
+    pub fn len(&self) -> usize {
+        self.content.len()
+    }
+    /// Number of sub-arrays pushed so far.
+    pub fn num_arrays(&self) -> usize {
+        self.indices.len() - 1
+    }
+    /// Returns `true` if no sub-array was pushed. Empty sub-arrays count.
+    pub fn is_empty(&self) -> bool {
+        self.num_arrays() == 0
+    }
+    /// Borrows the last pushed sub-array, or returns `None` if the
+    /// builder is empty.
+    pub fn last_array(&self) -> Option<&[T]> {
+        match self.indices.as_slice() {
+            [.., start, end] => Some(&self.content[*start..*end]),
+            _ => None,
+        }
+    }
+    /// Moves the sub-arrays of `other` to the end of `self`, leaving
+    /// `other` empty. The content is moved at once and only the offsets
+    /// of `other` are rebased.
+    pub fn append(&mut self, other: &mut Self) {
+        let base = self.content.len();
+        self.content.append(&mut other.content);
+        self.indices
+            .extend(other.indices.drain(1..).map(|offset| offset + base));
+    }
+    /// Removes the last pushed sub-array, truncating the content back to
+    /// the previous offset, and returns its elements. Returns `None` if
+    /// the builder is empty.
+    pub fn pop_last(&mut self) -> Option<Vec<T>> {
+        if self.indices.len() < 2 {
+            return None;
+        }
+        self.indices.pop();
+        let start = *self.indices.last()?;
+        Some(self.content.split_off(start))
+    }
+    /// Removes every sub-array, keeping the allocated buffers so that the
+    /// builder can be reused.
+    pub fn clear(&mut self) {
+        self.content.clear();
+        self.indices.clear();
+        self.indices.push(0);
+    }
+    /// Reserves room for at least `additional` more elements in the
+    /// content buffer. Like `Vec::reserve`, it may reserve more to avoid
+    /// frequent reallocations.
+    pub fn reserve(&mut self, additional: usize) {
+        self.content.reserve(additional);
+    }
+    /// Reserves room for at least `additional` more sub-arrays in the
+    /// offsets buffer. Like `Vec::reserve`, it may reserve more to avoid
+    /// frequent reallocations.
+    pub fn reserve_arrays(&mut self, additional: usize) {
+        self.indices.reserve(additional);
+    }
 
     pub fn build_flatvec(self) -> FlatVec<T> {
         FlatVec {
@@ -64,6 +187,15 @@ impl<T> FlatBuilder<T> {
             indices: self.indices,
         }
     }
+    /// Moves the pushed sub-arrays into `target`, swapping buffers with
+    /// it. The previous buffers of `target` are cleared and kept by the
+    /// builder, so that rebuilding a collection in a loop does not
+    /// allocate once the buffers are large enough.
+    pub fn build_into(&mut self, target: &mut FlatVec<T>) {
+        std::mem::swap(&mut self.content, &mut target.content);
+        std::mem::swap(&mut self.indices, &mut target.indices);
+        self.clear();
+    }
     pub fn build_flatarray(self) -> FlatArray<T> {
         FlatArray {
             content: self.content.into_boxed_slice(),
@@ -72,6 +204,21 @@ impl<T> FlatBuilder<T> {
     }
 }
 impl FlatBuilder<u8> {
+    /// Pushes the bytes of `s` as a new sub-array.
+    pub fn push_str(&mut self, s: &str) {
+        self.content.extend_from_slice(s.as_bytes());
+        self.indices.push(self.content.len());
+    }
+    /// Pushes the bytes of every string as a new sub-array.
+    pub fn push_strs<I, S>(&mut self, strings: I)
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        for s in strings {
+            self.push_str(s.as_ref());
+        }
+    }
     pub fn build_flatstr(self) -> FlatStr {
         FlatStr {
             content: self.content,
@@ -80,6 +227,16 @@ impl FlatBuilder<u8> {
     }
 }
 
+impl<T: Clone> FlatBuilder<T> {
+    /// Pushes a copy of `slice` as a new sub-array. Faster than `push`,
+    /// as the elements are copied at once (a `memcpy` for `Copy` types)
+    /// and a single offset is written.
+    pub fn push_slice(&mut self, slice: &[T]) {
+        self.content.extend_from_slice(slice);
+        self.indices.push(self.content.len());
+    }
+}
+
 impl<T: Default> FlatBuilder<T> {
     pub fn push_take<I>(&mut self, item: I)
     where
@@ -94,6 +251,20 @@ impl<T: Default> FlatBuilder<T> {
     }
 }
 
+/// Collects sub-arrays of fallible elements into a `FlatVec`, e.g. the
+/// tokens of sentences parsed on the fly. Returns the first error.
+pub fn try_collect<T, E, I, A>(arrays: I) -> Result<FlatVec<T>, E>
+where
+    I: IntoIterator<Item = A>,
+    A: IntoIterator<Item = Result<T, E>>,
+{
+    let mut builder = FlatBuilder::default();
+    for array in arrays {
+        builder.try_push_fallible(array)?;
+    }
+    Ok(builder.build_flatvec())
+}
+
 impl<T> Default for FlatBuilder<T> {
     fn default() -> Self {
         Self {
@@ -103,6 +274,33 @@ impl<T> Default for FlatBuilder<T> {
     }
 }
 
+impl<T, I: IntoIterator<Item = T>> Extend<I> for FlatBuilder<T> {
+    /// Pushes every item as a new sub-array.
+    fn extend<J: IntoIterator<Item = I>>(&mut self, iter: J) {
+        let iter = iter.into_iter();
+        self.reserve_arrays(iter.size_hint().0);
+        for item in iter {
+            self.push(item);
+        }
+    }
+}
+
+impl<T, I: IntoIterator<Item = T>> FromIterator<I> for FlatBuilder<T> {
+    /// Builds a builder with one sub-array per item.
+    fn from_iter<J: IntoIterator<Item = I>>(iter: J) -> Self {
+        let mut builder = Self::default();
+        builder.extend(iter);
+        builder
+    }
+}
+
+impl<T> From<(usize, usize)> for FlatBuilder<T> {
+    /// Same as `FlatBuilder::with_capacity(content_capacity, arrays_capacity)`.
+    fn from((content_capacity, arrays_capacity): (usize, usize)) -> Self {
+        Self::with_capacity(content_capacity, arrays_capacity)
+    }
+}
+
 impl<T> Clone for FlatBuilder<T>
 where
     T: Clone,
@@ -114,3 +312,196 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_push_str() {
+        let mut builder = FlatBuilder::default();
+        builder.push_str("hello");
+        builder.push_strs(["", "world"]);
+        builder.push_strs(vec![String::from("!")]);
+        let flat_str = builder.build_flatstr();
+        assert_eq!(vec![0, 5, 5, 10, 11], flat_str.indices);
+        assert_eq!(
+            vec!["hello", "", "world", "!"],
+            flat_str.iter_strings().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_with_capacity() {
+        let mut builder: FlatBuilder<u32> = FlatBuilder::with_capacity(10, 3);
+        assert!(builder.content.capacity() >= 10);
+        assert!(builder.indices.capacity() >= 4);
+        builder.push([1, 2]);
+        assert_eq!(vec![0, 2], builder.indices);
+        let builder: FlatBuilder<u32> = (5, 0).into();
+        assert!(builder.content.capacity() >= 5);
+        assert_eq!(vec![0], builder.indices);
+    }
+
+    #[test]
+    fn test_reserve() {
+        let mut builder: FlatBuilder<u32> = FlatBuilder::default();
+        builder.reserve(100);
+        builder.reserve_arrays(10);
+        assert!(builder.content.capacity() >= 100);
+        assert!(builder.indices.capacity() >= 11);
+        builder.push(0..100);
+        assert_eq!(vec![0, 100], builder.indices);
+    }
+
+    #[test]
+    fn test_pop_last() {
+        let mut builder = FlatBuilder::default();
+        builder.push([1, 2]);
+        builder.push([3, 4, 5]);
+        assert_eq!(Some(vec![3, 4, 5]), builder.pop_last());
+        builder.push([6]);
+        assert_eq!(vec![1, 2, 6], builder.content);
+        assert_eq!(vec![0, 2, 3], builder.indices);
+        assert_eq!(Some(vec![6]), builder.pop_last());
+        assert_eq!(Some(vec![1, 2]), builder.pop_last());
+        assert_eq!(None, builder.pop_last());
+        assert_eq!(vec![0], builder.indices);
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut builder = FlatBuilder::default();
+        builder.push([1, 2, 3]);
+        builder.push([4]);
+        let capacity = builder.content.capacity();
+        builder.clear();
+        assert!(builder.content.is_empty());
+        assert_eq!(vec![0], builder.indices);
+        assert_eq!(capacity, builder.content.capacity());
+        builder.push([5]);
+        assert_eq!(vec![0, 1], builder.indices);
+    }
+
+    #[test]
+    fn test_introspection() {
+        let mut builder = FlatBuilder::default();
+        assert!(builder.is_empty());
+        assert_eq!(None, builder.last_array());
+        builder.push([1, 2, 3]);
+        builder.push([]);
+        assert!(!builder.is_empty());
+        assert_eq!(3, builder.len());
+        assert_eq!(2, builder.num_arrays());
+        assert_eq!(Some(&[][..]), builder.last_array());
+        builder.push([4]);
+        assert_eq!(Some(&[4][..]), builder.last_array());
+    }
+
+    #[test]
+    fn test_extend_collect() {
+        let mut builder: FlatBuilder<u32> = vec![vec![1, 2], vec![3]].into_iter().collect();
+        builder.extend([0..2, 5..5]);
+        assert_eq!(vec![0, 2, 3, 5, 5], builder.indices);
+        assert_eq!(vec![1, 2, 3, 0, 1], builder.content);
+    }
+
+    #[test]
+    fn test_append() {
+        let mut builder: FlatBuilder<u32> = [vec![1, 2], vec![3]].into_iter().collect();
+        let mut other: FlatBuilder<u32> = [vec![], vec![4, 5]].into_iter().collect();
+        builder.append(&mut other);
+        assert_eq!(vec![0, 2, 3, 3, 5], builder.indices);
+        assert_eq!(vec![1, 2, 3, 4, 5], builder.content);
+        assert!(other.is_empty());
+        assert_eq!(vec![0], other.indices);
+    }
+
+    #[test]
+    fn test_push_slice() {
+        let mut builder = FlatBuilder::default();
+        builder.push_slice(&[1, 2, 3]);
+        builder.push_slice(&[]);
+        builder.push_slice(&[4]);
+        assert_eq!(vec![0, 3, 3, 4], builder.indices);
+        assert_eq!(vec![1, 2, 3, 4], builder.content);
+    }
+
+    /// Iterator reporting a wrong length.
+    struct Lying(std::ops::Range<u32>);
+
+    impl Iterator for Lying {
+        type Item = u32;
+        fn next(&mut self) -> Option<u32> {
+            self.0.next()
+        }
+    }
+
+    impl ExactSizeIterator for Lying {
+        fn len(&self) -> usize {
+            3
+        }
+    }
+
+    #[test]
+    fn test_try_push() {
+        let mut builder = FlatBuilder::default();
+        assert_eq!(Ok(()), builder.try_push([1, 2]));
+        assert_eq!(Ok(()), builder.try_push_exact_sized(Lying(0..3)));
+        assert_eq!(
+            Err(FlatError::LengthMismatch {
+                expected: 3,
+                actual: 5
+            }),
+            builder.try_push_exact_sized(Lying(0..5))
+        );
+        assert_eq!(vec![0, 2, 5], builder.indices);
+        assert_eq!(vec![1, 2, 0, 1, 2], builder.content);
+        builder.indices = vec![0, usize::MAX];
+        assert_eq!(Err(FlatError::OffsetOverflow), builder.try_push([3]));
+        assert_eq!(5, builder.len());
+    }
+
+    #[test]
+    fn test_build_into() {
+        let mut builder = FlatBuilder::default();
+        let mut target = FlatBuilder::default().build_flatvec();
+        for epoch in 0..3 {
+            builder.push([epoch; 2]);
+            builder.push_slice(&[epoch]);
+            builder.build_into(&mut target);
+            assert!(builder.is_empty());
+            assert_eq!(vec![0, 2, 3], target.indices);
+            assert_eq!(vec![epoch, epoch, epoch], target.content);
+        }
+        assert!(builder.content.capacity() >= 3);
+    }
+
+    #[test]
+    fn test_build_into_offset_target() {
+        let mut builder = FlatBuilder::default();
+        let mut target = FlatVec::from_raw(b"abc".to_vec(), vec![3, 3]);
+        builder.push(*b"x");
+        builder.build_into(&mut target);
+        assert_eq!(vec![0], builder.indices);
+        builder.push(*b"y");
+        assert_eq!(vec![0, 1], builder.indices);
+        assert_eq!(
+            vec![&b"y"[..]],
+            builder.build_flatvec().iter_arrays().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_try_push_fallible() {
+        let mut builder = FlatBuilder::default();
+        let parse = |s: &str| s.split(' ').map(str::parse::<u32>).collect::<Vec<_>>();
+        assert!(builder.try_push_fallible(parse("1 2")).is_ok());
+        assert!(builder.try_push_fallible(parse("3 x 4")).is_err());
+        assert_eq!(vec![0, 2], builder.indices);
+        assert_eq!(vec![1, 2], builder.content);
+        let flat_vec = try_collect(["1 2", "3"].map(parse)).unwrap();
+        assert_eq!(vec![0, 2, 3], flat_vec.indices);
+        assert!(try_collect(["1", "y"].map(parse)).is_err());
+    }
+}
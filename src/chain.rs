@@ -0,0 +1,186 @@
+use crate::iterator::{FlattenedCollection, Iter, get_offset, get_range};
+use crate::{FlatVec, FlatView};
+use std::ops::Range;
+
+/// Logical concatenation of several flat collections. The sub-arrays
+/// of the chain are the sub-arrays of each part, in order, and nothing
+/// is copied until `materialize` is called. Useful to iterate over
+/// several shards as if they were a single collection.
+#[derive(Debug, Clone)]
+pub struct FlatChain<'a, T> {
+    parts: Vec<FlatView<'a, T>>,
+    /// Offset of the first element of each part in the chain, followed
+    /// by the total number of elements.
+    content_starts: Vec<usize>,
+    /// Index of the first sub-array of each part in the chain, followed
+    /// by the total number of sub-arrays.
+    array_starts: Vec<usize>,
+}
+
+impl<T> Default for FlatChain<'_, T> {
+    fn default() -> Self {
+        Self {
+            parts: vec![],
+            content_starts: vec![0],
+            array_starts: vec![0],
+        }
+    }
+}
+
+impl<'a, T> FlatChain<'a, T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Appends a collection at the end of the chain. Only the elements
+    /// covered by its offsets are part of the chain.
+    pub fn push(&mut self, part: impl Into<FlatView<'a, T>>) {
+        let part = part.into();
+        let first = part.indices.first().copied().unwrap_or(0);
+        let last = part.indices.last().copied().unwrap_or(0);
+        let arrays = part.indices.len().saturating_sub(1);
+        self.parts.push(part);
+        self.content_starts
+            .push(self.content_starts.last().unwrap() + last - first);
+        self.array_starts
+            .push(self.array_starts.last().unwrap() + arrays);
+    }
+    /// Number of collections in the chain.
+    pub fn num_parts(&self) -> usize {
+        self.parts.len()
+    }
+    /// Number of sub-arrays in the chain.
+    pub fn num_arrays(&self) -> usize {
+        *self.array_starts.last().unwrap()
+    }
+    /// Returns an iterator over the sub-arrays of every part of the
+    /// chain. The iterator will return a slice of type `&[T]`.
+    pub fn iter_arrays(&self) -> Iter<'_, FlatChain<'a, T>, T> {
+        Iter::new(self)
+    }
+    /// Returns an iterator over the elements of every part of the
+    /// chain.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.parts.iter().flat_map(|part| part.get_content())
+    }
+    /// Copies the chain into a single `FlatVec`.
+    pub fn materialize(&self) -> FlatVec<T>
+    where
+        T: Clone,
+    {
+        let mut content = Vec::with_capacity(*self.content_starts.last().unwrap());
+        let mut indices = Vec::with_capacity(self.num_arrays() + 1);
+        indices.push(0);
+        for array in self.iter_arrays() {
+            content.extend_from_slice(array);
+            indices.push(content.len());
+        }
+        FlatVec { content, indices }
+    }
+
+    /// Index of the part containing the element or offset `position`,
+    /// given the start positions of the parts.
+    fn find_part(starts: &[usize], position: usize) -> usize {
+        starts[..starts.len() - 1]
+            .partition_point(|&s| s <= position)
+            .saturating_sub(1)
+    }
+}
+
+impl<'a, T> FromIterator<FlatView<'a, T>> for FlatChain<'a, T> {
+    fn from_iter<I: IntoIterator<Item = FlatView<'a, T>>>(iter: I) -> Self {
+        let mut chain = Self::default();
+        for part in iter {
+            chain.push(part);
+        }
+        chain
+    }
+}
+
+impl<T> FlattenedCollection<T> for FlatChain<'_, T> {
+    fn indices_len(&self) -> usize {
+        self.num_arrays() + 1
+    }
+    fn content_len(&self) -> usize {
+        *self.content_starts.last().unwrap()
+    }
+    unsafe fn get_indices(&self, index: usize) -> usize {
+        let p = Self::find_part(&self.array_starts, index);
+        let j = index - self.array_starts[p];
+        if j == 0 {
+            return self.content_starts[p];
+        }
+        let part = &self.parts[p];
+        unsafe {
+            self.content_starts[p] + get_offset(part.indices, j) - get_offset(part.indices, 0)
+        }
+    }
+    /// # Safety
+    /// The range must also lie inside a single part of the chain, as the
+    /// sub-arrays do.
+    unsafe fn get_content(&self, range: Range<usize>) -> &[T] {
+        let p = Self::find_part(&self.content_starts, range.start);
+        let part = &self.parts[p];
+        let first = part.indices.first().copied().unwrap_or(0);
+        let start = range.start - self.content_starts[p] + first;
+        let end = range.end - self.content_starts[p] + first;
+        unsafe { get_range(part.content, start..end) }
+    }
+    /// Returns the content in `range`, or `None` if `range` is
+    /// decreasing, goes past the end of the content or spans several
+    /// parts, whose contents are not contiguous.
+    fn try_get_content(&self, range: Range<usize>) -> Option<&[T]> {
+        if range.start > range.end || range.end > self.content_len() {
+            return None;
+        }
+        let p = Self::find_part(&self.content_starts, range.start);
+        if range.end > self.content_starts[p + 1] {
+            return None;
+        }
+        unsafe { Some(self.get_content(range)) }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::FlatArray;
+
+    #[test]
+    fn test_iter_chain() {
+        let first = FlatVec::new(vec![vec![1, 2], vec![]]);
+        let empty = FlatArray::default();
+        let second = FlatArray::new(vec![vec![3], vec![4, 5, 6]]);
+        let mut chain = FlatChain::new();
+        chain.push(&first);
+        chain.push(&empty);
+        chain.push(&second);
+        let actual: Vec<_> = chain.iter_arrays().collect();
+        let expected: Vec<&[i32]> = vec![&[1, 2], &[], &[3], &[4, 5, 6]];
+        assert_eq!(expected, actual);
+        assert_eq!(
+            vec![1, 2, 3, 4, 5, 6],
+            chain.iter().copied().collect::<Vec<_>>()
+        );
+        let materialized = chain.materialize();
+        assert_eq!(
+            FlatVec::new(vec![vec![1, 2], vec![], vec![3], vec![4, 5, 6]]),
+            materialized
+        );
+    }
+
+    #[test]
+    fn test_chain_content_across_parts() {
+        let first = FlatVec::new(vec![vec![1, 2], vec![]]);
+        let empty = FlatArray::default();
+        let second = FlatArray::new(vec![vec![3], vec![4, 5, 6]]);
+        let chain: FlatChain<_> = [(&first).into(), (&empty).into(), (&second).into()]
+            .into_iter()
+            .collect();
+        assert_eq!(Some(&[1, 2][..]), chain.try_get_content(0..2));
+        assert_eq!(Some(&[][..]), chain.try_get_content(2..2));
+        assert_eq!(Some(&[3, 4][..]), chain.try_get_content(2..4));
+        assert_eq!(None, chain.try_get_content(1..3));
+        assert_eq!(None, chain.try_get_content(0..6));
+        assert_eq!(None, chain.try_get_content(5..7));
+    }
+}
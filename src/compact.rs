@@ -0,0 +1,175 @@
+use crate::{FlatArray, FlatVec};
+
+/// Number of sub-arrays between two absolute checkpoints.
+const CHECKPOINT_INTERVAL: usize = 64;
+
+/// Flat collection storing the lengths of its sub-arrays instead of
+/// their offsets, as LEB128 variable-length integers: short sub-arrays
+/// take a single byte of offsets instead of eight. Every
+/// `CHECKPOINT_INTERVAL` sub-arrays, the absolute position is recorded,
+/// so that accessing a sub-array decodes at most `CHECKPOINT_INTERVAL`
+/// lengths. Iterating decodes the lengths on the fly.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CompactFlatArray<T> {
+    content: Box<[T]>,
+    /// LEB128-encoded lengths of the sub-arrays.
+    lengths: Box<[u8]>,
+    /// For every `CHECKPOINT_INTERVAL`-th sub-array, its offset in
+    /// `content` and the position of its length in `lengths`.
+    checkpoints: Box<[(usize, usize)]>,
+    num_arrays: usize,
+}
+
+/// Appends `value` to `bytes` as a LEB128 integer.
+fn write_varint(bytes: &mut Vec<u8>, mut value: usize) {
+    while value >= 0x80 {
+        bytes.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+/// Decodes the LEB128 integer starting at `*pos`, moving `*pos` past it.
+fn read_varint(bytes: &[u8], pos: &mut usize) -> usize {
+    let mut value = 0;
+    let mut shift = 0;
+    loop {
+        let byte = bytes[*pos];
+        *pos += 1;
+        value |= usize::from(byte & 0x7f) << shift;
+        if byte < 0x80 {
+            return value;
+        }
+        shift += 7;
+    }
+}
+
+impl<T> CompactFlatArray<T> {
+    /// Compresses the offsets of the sub-arrays delimited by `indices`.
+    fn from_parts(content: Box<[T]>, indices: &[usize]) -> Self {
+        let num_arrays = indices.len().saturating_sub(1);
+        let mut lengths = Vec::with_capacity(num_arrays);
+        let mut checkpoints = Vec::with_capacity(num_arrays.div_ceil(CHECKPOINT_INTERVAL));
+        for (i, w) in indices.windows(2).enumerate() {
+            if i % CHECKPOINT_INTERVAL == 0 {
+                checkpoints.push((w[0], lengths.len()));
+            }
+            write_varint(&mut lengths, w[1] - w[0]);
+        }
+        Self {
+            content,
+            lengths: lengths.into_boxed_slice(),
+            checkpoints: checkpoints.into_boxed_slice(),
+            num_arrays,
+        }
+    }
+    /// Number of sub-arrays.
+    pub fn num_arrays(&self) -> usize {
+        self.num_arrays
+    }
+    /// Returns the i-th sub-array, or `None` if `i` is out of bounds.
+    pub fn get(&self, i: usize) -> Option<&[T]> {
+        if i >= self.num_arrays {
+            return None;
+        }
+        let (mut start, mut pos) = self.checkpoints[i / CHECKPOINT_INTERVAL];
+        for _ in 0..i % CHECKPOINT_INTERVAL {
+            start += read_varint(&self.lengths, &mut pos);
+        }
+        let length = read_varint(&self.lengths, &mut pos);
+        Some(&self.content[start..start + length])
+    }
+    /// Returns an iterator over the sub-arrays, decoding their lengths on
+    /// the fly.
+    pub fn iter_arrays(&self) -> impl Iterator<Item = &[T]> {
+        let mut start = self.first_offset();
+        let mut pos = 0;
+        (0..self.num_arrays).map(move |_| {
+            let length = read_varint(&self.lengths, &mut pos);
+            let array = &self.content[start..start + length];
+            start += length;
+            array
+        })
+    }
+    /// Offset of the first sub-array in the content.
+    fn first_offset(&self) -> usize {
+        self.checkpoints.first().map_or(0, |&(offset, _)| offset)
+    }
+    /// Borrows the content.
+    pub fn get_content(&self) -> &[T] {
+        &self.content
+    }
+    /// Number of heap bytes allocated for the content, the lengths and the
+    /// checkpoints.
+    pub fn mem_usage(&self) -> usize {
+        size_of_val(&*self.content) + size_of_val(&*self.lengths) + size_of_val(&*self.checkpoints)
+    }
+    /// Decompresses the offsets into a `FlatArray`.
+    pub fn into_flatarray(self) -> FlatArray<T> {
+        let mut indices = Vec::with_capacity(self.num_arrays + 1);
+        indices.push(self.first_offset());
+        let mut pos = 0;
+        for _ in 0..self.num_arrays {
+            let offset = indices.last().unwrap() + read_varint(&self.lengths, &mut pos);
+            indices.push(offset);
+        }
+        FlatArray {
+            content: self.content,
+            indices: indices.into_boxed_slice(),
+        }
+    }
+}
+
+impl<T> From<FlatArray<T>> for CompactFlatArray<T> {
+    fn from(value: FlatArray<T>) -> Self {
+        Self::from_parts(value.content, &value.indices)
+    }
+}
+
+impl<T> From<FlatVec<T>> for CompactFlatArray<T> {
+    fn from(value: FlatVec<T>) -> Self {
+        Self::from_parts(value.content.into_boxed_slice(), &value.indices)
+    }
+}
+
+impl<T> From<CompactFlatArray<T>> for FlatArray<T> {
+    fn from(value: CompactFlatArray<T>) -> Self {
+        value.into_flatarray()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn build_arrays() -> Vec<Vec<u32>> {
+        (0..200).map(|i| (0..(i * 7) % 300).collect()).collect()
+    }
+
+    #[test]
+    fn test_compact_access() {
+        let arrays = build_arrays();
+        let compact = CompactFlatArray::from(FlatVec::new(arrays.clone()));
+        assert_eq!(200, compact.num_arrays());
+        for (i, array) in arrays.iter().enumerate() {
+            assert_eq!(Some(array.as_slice()), compact.get(i));
+        }
+        assert_eq!(None, compact.get(200));
+        assert!(
+            compact
+                .iter_arrays()
+                .eq(arrays.iter().map(|a| a.as_slice()))
+        );
+    }
+
+    #[test]
+    fn test_compact_round_trip() {
+        let flat_array = FlatArray::new(build_arrays());
+        let compact = CompactFlatArray::from(flat_array.clone());
+        assert!(compact.mem_usage() < flat_array.mem_usage());
+        assert_eq!(flat_array, compact.into_flatarray());
+        let empty = CompactFlatArray::from(FlatVec::<u8>::new_empty());
+        assert_eq!(0, empty.iter_arrays().count());
+        assert_eq!(&[0][..], &*empty.into_flatarray().indices);
+    }
+}
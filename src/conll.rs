@@ -0,0 +1,158 @@
+//! Reader and writer for CoNLL-style files: one token per line, with
+//! the token in the first column and its label in the last one, and
+//! sentences separated by blank lines. `-DOCSTART-` lines are skipped.
+use crate::{FlatBuilder, FlatInterner, FlatStr, FlatVec};
+use std::io::{self, BufRead, Write};
+
+/// Tokens and labels of a CoNLL file. `tokens` and `labels` hold one
+/// string per token, and share the sentence offsets.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conll {
+    tokens: FlatStr,
+    labels: FlatStr,
+    /// Offsets of the sentences in `tokens` and `labels`.
+    sentences: Vec<usize>,
+}
+
+impl Conll {
+    /// Parses a CoNLL file. Fails with `io::ErrorKind::InvalidData` if a
+    /// line has a single column.
+    pub fn from_reader(mut reader: impl BufRead) -> io::Result<Self> {
+        let mut tokens = FlatBuilder::default();
+        let mut labels = FlatBuilder::default();
+        let mut sentences = vec![0];
+        let mut count = 0;
+        let mut line = String::new();
+        let mut line_number = 0;
+        loop {
+            line.clear();
+            let end = reader.read_line(&mut line)? == 0;
+            line_number += 1;
+            let trimmed = line.trim();
+            if trimmed.is_empty() || end {
+                if *sentences.last().unwrap() != count {
+                    sentences.push(count);
+                }
+                if end {
+                    break;
+                }
+                continue;
+            }
+            if trimmed.starts_with("-DOCSTART-") {
+                continue;
+            }
+            let mut columns = trimmed.split_whitespace();
+            let token = columns.next().unwrap();
+            let Some(label) = columns.next_back() else {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("line {line_number} has no label column"),
+                ));
+            };
+            tokens.push(token.bytes());
+            labels.push(label.bytes());
+            count += 1;
+        }
+        Ok(Self {
+            tokens: tokens.build_flatstr(),
+            labels: labels.build_flatstr(),
+            sentences,
+        })
+    }
+    /// Writes the tokens and labels back, one `token label` line per
+    /// token and a blank line after each sentence.
+    pub fn write_to(&self, mut writer: impl Write) -> io::Result<()> {
+        for sentence in self.iter_sentences() {
+            for (token, label) in sentence {
+                writeln!(writer, "{token} {label}")?;
+            }
+            writeln!(writer)?;
+        }
+        Ok(())
+    }
+    /// Number of sentences.
+    pub fn num_sentences(&self) -> usize {
+        self.sentences.len() - 1
+    }
+    /// Borrows the tokens, one string per token.
+    pub fn tokens(&self) -> &FlatStr {
+        &self.tokens
+    }
+    /// Borrows the labels, one string per token.
+    pub fn labels(&self) -> &FlatStr {
+        &self.labels
+    }
+    /// Borrows the offsets of the sentences in the tokens and labels.
+    pub fn sentence_offsets(&self) -> &[usize] {
+        &self.sentences
+    }
+    /// Returns an iterator over the sentences. Each sentence is an
+    /// iterator over its `(token, label)` pairs.
+    pub fn iter_sentences(&self) -> impl Iterator<Item = impl Iterator<Item = (&str, &str)>> {
+        self.sentences
+            .windows(2)
+            .map(|w| (w[0]..w[1]).map(|i| (string_at(&self.tokens, i), string_at(&self.labels, i))))
+    }
+}
+
+impl Conll {
+    /// Maps every label to its dense integer id in `vocab`, interning the
+    /// unknown labels, with one array of ids per sentence.
+    pub fn encode_labels(&self, vocab: &mut FlatInterner) -> FlatVec<u32> {
+        FlatVec {
+            content: self.labels.encode_labels(vocab),
+            indices: self.sentences.clone(),
+        }
+    }
+}
+
+fn string_at(flat_str: &FlatStr, i: usize) -> &str {
+    let bytes = &flat_str.content[flat_str.indices[i]..flat_str.indices[i + 1]];
+    str::from_utf8(bytes).expect("built from strings")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const INPUT: &str = "-DOCSTART- -X- O O\n\nEU NNP B-ORG\nrejects VBZ O\n\n\nPeter NNP B-PER\n";
+
+    #[test]
+    fn test_read_conll() {
+        let conll = Conll::from_reader(INPUT.as_bytes()).unwrap();
+        assert_eq!(2, conll.num_sentences());
+        assert_eq!(&[0, 2, 3], conll.sentence_offsets());
+        let sentences: Vec<Vec<_>> = conll.iter_sentences().map(|s| s.collect()).collect();
+        let expected = vec![
+            vec![("EU", "B-ORG"), ("rejects", "O")],
+            vec![("Peter", "B-PER")],
+        ];
+        assert_eq!(expected, sentences);
+    }
+
+    #[test]
+    fn test_encode_conll_labels() {
+        let conll = Conll::from_reader(INPUT.as_bytes()).unwrap();
+        let mut vocab = FlatInterner::new();
+        let encoded = conll.encode_labels(&mut vocab);
+        assert_eq!(
+            vec![vec![0, 1], vec![2]],
+            encoded
+                .iter_arrays()
+                .map(|a| a.to_vec())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!("B-PER", vocab.resolve(2));
+    }
+
+    #[test]
+    fn test_write_conll() {
+        let conll = Conll::from_reader(INPUT.as_bytes()).unwrap();
+        let mut output = Vec::new();
+        conll.write_to(&mut output).unwrap();
+        let written = String::from_utf8(output).unwrap();
+        assert_eq!("EU B-ORG\nrejects O\n\nPeter B-PER\n\n", written);
+        assert_eq!(conll, Conll::from_reader(written.as_bytes()).unwrap());
+        assert!(Conll::from_reader("EU\n".as_bytes()).is_err());
+    }
+}
@@ -0,0 +1,151 @@
+use crate::iterator::{FlattenedCollection, FlattenedCollectionMut, Iter, IterMut};
+use crate::{FlatArray, FlatVec, FlatView};
+use std::ops::{Deref, Range};
+
+/// Copy-on-write flat collection. It starts by borrowing an existing
+/// collection and only copies it into an owned `FlatVec` when a
+/// mutating method is first called.
+#[derive(Debug)]
+pub enum FlatCow<'a, T> {
+    Borrowed(FlatView<'a, T>),
+    Owned(FlatVec<T>),
+}
+
+impl<T: Clone> FlattenedCollection<T> for FlatCow<'_, T> {
+    fn indices_len(&self) -> usize {
+        match self {
+            FlatCow::Borrowed(view) => view.indices_len(),
+            FlatCow::Owned(flat_vec) => flat_vec.indices_len(),
+        }
+    }
+    fn content_len(&self) -> usize {
+        match self {
+            FlatCow::Borrowed(view) => view.content_len(),
+            FlatCow::Owned(flat_vec) => flat_vec.content_len(),
+        }
+    }
+    unsafe fn get_indices(&self, index: usize) -> usize {
+        match self {
+            FlatCow::Borrowed(view) => unsafe { view.get_indices(index) },
+            FlatCow::Owned(flat_vec) => unsafe { flat_vec.get_indices(index) },
+        }
+    }
+    unsafe fn get_content(&self, range: Range<usize>) -> &[T] {
+        match self {
+            FlatCow::Borrowed(view) => unsafe { FlattenedCollection::get_content(view, range) },
+            FlatCow::Owned(flat_vec) => unsafe { flat_vec.get_content(range) },
+        }
+    }
+}
+
+impl<T: Clone> FlattenedCollectionMut<T> for FlatCow<'_, T> {
+    /// Materializes the owned `FlatVec` if the content is still
+    /// borrowed.
+    unsafe fn get_mut_content(&mut self, range: Range<usize>) -> &mut [T] {
+        unsafe { self.to_mut().get_mut_content(range) }
+    }
+}
+
+impl<'a, T: Clone> FlatCow<'a, T> {
+    /// Returns `true` if the content is still borrowed.
+    pub fn is_borrowed(&self) -> bool {
+        matches!(self, FlatCow::Borrowed(_))
+    }
+    /// Returns `true` if the content has been copied.
+    pub fn is_owned(&self) -> bool {
+        matches!(self, FlatCow::Owned(_))
+    }
+    /// Returns a mutable reference to the owned `FlatVec`, cloning the
+    /// borrowed content first if needed.
+    pub fn to_mut(&mut self) -> &mut FlatVec<T> {
+        if let FlatCow::Borrowed(view) = *self {
+            let first = view.indices.first().copied().unwrap_or(0);
+            *self = FlatCow::Owned(FlatVec {
+                content: view.get_content().to_vec(),
+                indices: view.indices.iter().map(|i| i - first).collect(),
+            });
+        }
+        match self {
+            FlatCow::Borrowed(_) => unreachable!(),
+            FlatCow::Owned(flat_vec) => flat_vec,
+        }
+    }
+    /// Extracts the owned `FlatVec`, cloning the borrowed content if
+    /// needed.
+    pub fn into_owned(mut self) -> FlatVec<T> {
+        self.to_mut();
+        match self {
+            FlatCow::Borrowed(_) => unreachable!(),
+            FlatCow::Owned(flat_vec) => flat_vec,
+        }
+    }
+    /// Returns an iterator over the content. This iterator returns the
+    /// individual elements.
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.deref().iter()
+    }
+    /// Returns an iterator over the arrays of the collection. The
+    /// iterator will return a slice of type `&[T]`.
+    pub fn iter_arrays(&self) -> Iter<'_, FlatCow<'a, T>, T> {
+        Iter::new(self)
+    }
+    /// Returns an iterator over the arrays of the collection. The
+    /// iterator will return a slice of type `&mut [T]`. This copies the
+    /// borrowed content first.
+    pub fn iter_arrays_mut(&mut self) -> IterMut<'_, FlatVec<T>, T> {
+        self.to_mut().iter_arrays_mut()
+    }
+}
+
+impl<T> Deref for FlatCow<'_, T> {
+    type Target = [T];
+    fn deref(&self) -> &Self::Target {
+        match self {
+            FlatCow::Borrowed(view) => view.get_content(),
+            FlatCow::Owned(flat_vec) => &flat_vec.content,
+        }
+    }
+}
+
+impl<'a, T> From<FlatView<'a, T>> for FlatCow<'a, T> {
+    fn from(value: FlatView<'a, T>) -> Self {
+        FlatCow::Borrowed(value)
+    }
+}
+
+impl<'a, T> From<&'a FlatArray<T>> for FlatCow<'a, T> {
+    fn from(value: &'a FlatArray<T>) -> Self {
+        FlatCow::Borrowed(value.into())
+    }
+}
+
+impl<'a, T> From<&'a FlatVec<T>> for FlatCow<'a, T> {
+    fn from(value: &'a FlatVec<T>) -> Self {
+        FlatCow::Borrowed(value.into())
+    }
+}
+
+impl<T> From<FlatVec<T>> for FlatCow<'_, T> {
+    fn from(value: FlatVec<T>) -> Self {
+        FlatCow::Owned(value)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_copy_on_write() {
+        let flat_array = FlatArray::new(vec![vec![1, 2], vec![3]]);
+        let mut cow = FlatCow::from(&flat_array);
+        assert_eq!(2, cow.iter_arrays().count());
+        assert!(cow.is_borrowed());
+        for array in cow.iter_arrays_mut() {
+            array[0] += 10;
+        }
+        assert!(cow.is_owned());
+        assert_eq!(FlatVec::new(vec![vec![11, 2], vec![13]]), cow.into_owned());
+        assert_eq!(&[1, 2, 3], &flat_array[..]);
+    }
+}
@@ -0,0 +1,102 @@
+use crate::FlatVec;
+use std::ops::{Add, Mul};
+
+/// Sparse matrix in compressed sparse row (CSR) format. The offsets of
+/// a `FlatVec` are exactly the row pointers of a CSR matrix: the i-th
+/// sub-array of `values` holds the non-zero values of row i, and the
+/// i-th sub-array of `columns` their column indices.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsrMatrix<T> {
+    values: FlatVec<T>,
+    columns: FlatVec<usize>,
+    ncols: usize,
+}
+
+impl<T> CsrMatrix<T> {
+    /// Builds a `nrows x ncols` matrix, `nrows` being the number of
+    /// sub-arrays of `values`. Returns `None` if `values` and `columns`
+    /// do not have the same offsets or if a column index is out of
+    /// bounds.
+    pub fn new(values: FlatVec<T>, columns: FlatVec<usize>, ncols: usize) -> Option<Self> {
+        if values.indices != columns.indices
+            || values.content.len() != columns.content.len()
+            || columns.content.iter().any(|&c| c >= ncols)
+        {
+            return None;
+        }
+        Some(Self {
+            values,
+            columns,
+            ncols,
+        })
+    }
+    /// Number of rows.
+    pub fn nrows(&self) -> usize {
+        self.values.indices.len().saturating_sub(1)
+    }
+    /// Number of columns.
+    pub fn ncols(&self) -> usize {
+        self.ncols
+    }
+    /// Number of stored values.
+    pub fn nnz(&self) -> usize {
+        self.values.content.len()
+    }
+    /// Returns an iterator over the rows. Each row is given as its
+    /// column indices and its values.
+    pub fn iter_rows(&self) -> impl Iterator<Item = (&[usize], &[T])> {
+        self.columns.iter_arrays().zip(self.values.iter_arrays())
+    }
+    /// Splits the matrix back into its values and column indices.
+    pub fn into_parts(self) -> (FlatVec<T>, FlatVec<usize>) {
+        (self.values, self.columns)
+    }
+}
+
+impl<T> CsrMatrix<T>
+where
+    T: Copy + Default + Add<Output = T> + Mul<Output = T>,
+{
+    /// Computes the sparse-dense product `self * x`.
+    ///
+    /// # Panics
+    /// Panics if `x.len()` is not the number of columns.
+    pub fn mul_vec(&self, x: &[T]) -> Vec<T> {
+        assert_eq!(self.ncols, x.len(), "dimension mismatch");
+        self.iter_rows()
+            .map(|(columns, values)| {
+                columns
+                    .iter()
+                    .zip(values)
+                    .fold(T::default(), |acc, (&c, &v)| acc + v * x[c])
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_mul_vec() {
+        // [[1, 0, 2],
+        //  [0, 0, 0],
+        //  [0, 3, 0]]
+        let values = FlatVec::new(vec![vec![1, 2], vec![], vec![3]]);
+        let columns = FlatVec::new(vec![vec![0, 2], vec![], vec![1]]);
+        let matrix = CsrMatrix::new(values, columns, 3).unwrap();
+        assert_eq!(3, matrix.nrows());
+        assert_eq!(3, matrix.nnz());
+        assert_eq!(vec![7, 0, 6], matrix.mul_vec(&[1, 2, 3]));
+    }
+
+    #[test]
+    fn test_invalid_shape() {
+        let values = FlatVec::new(vec![vec![1, 2]]);
+        let columns = FlatVec::new(vec![vec![0], vec![1]]);
+        assert!(CsrMatrix::new(values.clone(), columns, 3).is_none());
+        let columns = FlatVec::new(vec![vec![0, 3]]);
+        assert!(CsrMatrix::new(values, columns, 3).is_none());
+    }
+}
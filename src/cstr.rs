@@ -0,0 +1,114 @@
+use crate::error::FlatError;
+use std::ffi::{CStr, c_char};
+
+/// Collection of C strings stored in a single buffer. Every string is
+/// NUL-terminated and NUL-free internally, so the whole collection can
+/// be handed to C libraries, e.g. as a `(char**, len)` pair built with
+/// `as_ptrs`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FlatCStr {
+    /// The strings, each one followed by its NUL terminator.
+    content: Vec<u8>,
+    /// Offsets of the strings in `content`. The terminators are included
+    /// in the ranges.
+    indices: Vec<usize>,
+}
+
+impl Default for FlatCStr {
+    fn default() -> Self {
+        Self {
+            content: Vec::new(),
+            indices: vec![0],
+        }
+    }
+}
+
+impl FlatCStr {
+    /// Copies the strings, appending a NUL terminator to each of them.
+    /// Fails with `FlatError::InteriorNul` if a string contains a NUL
+    /// byte.
+    pub fn from_strings<S: AsRef<[u8]>, I: IntoIterator<Item = S>>(
+        strings: I,
+    ) -> Result<Self, FlatError> {
+        let mut flat = Self::default();
+        for (index, s) in strings.into_iter().enumerate() {
+            let bytes = s.as_ref();
+            if let Some(position) = bytes.iter().position(|&b| b == 0) {
+                return Err(FlatError::InteriorNul { index, position });
+            }
+            flat.content.extend_from_slice(bytes);
+            flat.content.push(0);
+            flat.indices.push(flat.content.len());
+        }
+        Ok(flat)
+    }
+    /// Copies the C strings, terminators included.
+    pub fn from_cstrs<'a, I: IntoIterator<Item = &'a CStr>>(strings: I) -> Self {
+        let mut flat = Self::default();
+        for s in strings {
+            flat.content.extend_from_slice(s.to_bytes_with_nul());
+            flat.indices.push(flat.content.len());
+        }
+        flat
+    }
+    /// Number of strings.
+    pub fn len(&self) -> usize {
+        self.indices.len() - 1
+    }
+    /// Returns `true` if there are no strings.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// Returns the i-th string, or `None` if `i` is out of bounds.
+    pub fn get(&self, i: usize) -> Option<&CStr> {
+        let start = *self.indices.get(i)?;
+        let end = *self.indices.get(i + 1)?;
+        Some(self.cstr(start, end))
+    }
+    /// Returns an iterator over the strings.
+    pub fn iter_cstrs(&self) -> impl Iterator<Item = &CStr> {
+        self.indices.windows(2).map(|w| self.cstr(w[0], w[1]))
+    }
+    /// Returns a pointer to the start of every string, e.g. to pass the
+    /// collection as a `char**` argument. The pointers are valid as long
+    /// as `self` is neither dropped nor modified.
+    pub fn as_ptrs(&self) -> Vec<*const c_char> {
+        self.iter_cstrs().map(CStr::as_ptr).collect()
+    }
+    /// Borrows the content buffer, terminators included.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.content
+    }
+    fn cstr(&self, start: usize, end: usize) -> &CStr {
+        // SAFETY: every string ends with its terminator and contains no
+        // other NUL byte.
+        unsafe { CStr::from_bytes_with_nul_unchecked(&self.content[start..end]) }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_flat_cstr() {
+        let flat = FlatCStr::from_strings(["hello", "", "world"]).unwrap();
+        assert_eq!(3, flat.len());
+        assert_eq!(b"hello\0\0world\0", flat.as_bytes());
+        assert_eq!(Some(c"world"), flat.get(2));
+        assert_eq!(
+            vec![c"hello", c"", c"world"],
+            flat.iter_cstrs().collect::<Vec<_>>()
+        );
+        let ptrs = flat.as_ptrs();
+        assert_eq!(c"world", unsafe { CStr::from_ptr(ptrs[2]) });
+        assert_eq!(flat, FlatCStr::from_cstrs([c"hello", c"", c"world"]));
+        assert_eq!(
+            Err(FlatError::InteriorNul {
+                index: 1,
+                position: 2
+            }),
+            FlatCStr::from_strings(["ok", "no\0pe"])
+        );
+    }
+}
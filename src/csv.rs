@@ -0,0 +1,176 @@
+//! Column loaders for delimited files, such as CSV or TSV exports of
+//! annotations. Records follow RFC 4180: a field can be quoted with
+//! `"`, in which case it can contain the delimiter, line breaks and
+//! doubled quotes, and records end with `\n` or `\r\n`.
+use crate::{FlatBuilder, FlatStr, FlatVec};
+use std::error::Error;
+use std::io::{self, BufRead};
+
+fn invalid_data(error: impl Into<Box<dyn Error + Send + Sync>>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+/// Reads the next record into `fields`. Returns `false` at the end of
+/// the input.
+fn read_record(
+    reader: &mut impl BufRead,
+    delimiter: u8,
+    fields: &mut Vec<String>,
+) -> io::Result<bool> {
+    fields.clear();
+    let mut line = Vec::new();
+    if reader.read_until(b'\n', &mut line)? == 0 {
+        return Ok(false);
+    }
+    let mut field = Vec::new();
+    let mut quoted = false;
+    let mut i = 0;
+    loop {
+        if i == line.len() {
+            if !quoted {
+                break;
+            }
+            // The quoted field goes on over the next line.
+            if reader.read_until(b'\n', &mut line)? == 0 {
+                return Err(invalid_data("unterminated quoted field"));
+            }
+        }
+        let byte = line[i];
+        i += 1;
+        match byte {
+            b'"' if quoted && line.get(i) == Some(&b'"') => {
+                field.push(b'"');
+                i += 1;
+            }
+            b'"' if quoted => quoted = false,
+            _ if quoted => field.push(byte),
+            b'"' if field.is_empty() => quoted = true,
+            b'\n' => break,
+            b'\r' if line.get(i) == Some(&b'\n') => {}
+            _ if byte == delimiter => {
+                fields.push(String::from_utf8(std::mem::take(&mut field)).map_err(invalid_data)?)
+            }
+            _ => field.push(byte),
+        }
+    }
+    fields.push(String::from_utf8(field).map_err(invalid_data)?);
+    Ok(true)
+}
+
+/// Returns the field at `column`, or an error naming the record.
+fn get_field(fields: &[String], column: usize, record: usize) -> io::Result<&str> {
+    fields
+        .get(column)
+        .map(String::as_str)
+        .ok_or_else(|| invalid_data(format!("record {record} has no column {column}")))
+}
+
+impl FlatStr {
+    /// Reads a delimited file and collects its `column`-th field (from
+    /// 0) into a `FlatStr`, one string per record. If `has_header` is
+    /// `true`, the first record is skipped. Fails with
+    /// `io::ErrorKind::InvalidData` if a record is too short, is not
+    /// valid UTF-8 or ends inside a quoted field.
+    pub fn from_csv_column(
+        mut reader: impl BufRead,
+        delimiter: u8,
+        has_header: bool,
+        column: usize,
+    ) -> io::Result<Self> {
+        let mut builder = FlatBuilder::default();
+        let mut fields = Vec::new();
+        let mut record = 0;
+        while read_record(&mut reader, delimiter, &mut fields)? {
+            record += 1;
+            if has_header && record == 1 {
+                continue;
+            }
+            builder.push(get_field(&fields, column, record)?.bytes());
+        }
+        Ok(builder.build_flatstr())
+    }
+}
+
+impl<T> FlatVec<T> {
+    /// Reads a delimited file whose records are sorted by `key_column`,
+    /// and groups the `value_column` of each run of records sharing the
+    /// same key into a sub-array, parsing the values with `parse`, e.g.
+    /// to load the labels of each sentence. Returns the keys of the
+    /// runs, in order, along with the grouped values. See
+    /// `FlatStr::from_csv_column` for `has_header` and the errors; a
+    /// value that cannot be parsed also fails with
+    /// `io::ErrorKind::InvalidData`.
+    pub fn from_csv_grouped<E, F>(
+        mut reader: impl BufRead,
+        delimiter: u8,
+        has_header: bool,
+        key_column: usize,
+        value_column: usize,
+        mut parse: F,
+    ) -> io::Result<(Vec<String>, Self)>
+    where
+        E: Into<Box<dyn Error + Send + Sync>>,
+        F: FnMut(&str) -> Result<T, E>,
+    {
+        let mut keys: Vec<String> = Vec::new();
+        let mut builder = FlatBuilder::default();
+        let mut values = Vec::new();
+        let mut fields = Vec::new();
+        let mut record = 0;
+        while read_record(&mut reader, delimiter, &mut fields)? {
+            record += 1;
+            if has_header && record == 1 {
+                continue;
+            }
+            let key = get_field(&fields, key_column, record)?;
+            let value = parse(get_field(&fields, value_column, record)?).map_err(invalid_data)?;
+            if keys.last().map(String::as_str) != Some(key) {
+                if !keys.is_empty() {
+                    builder.push(values.drain(..));
+                }
+                keys.push(key.to_string());
+            }
+            values.push(value);
+        }
+        if !keys.is_empty() {
+            builder.push(values);
+        }
+        Ok((keys, builder.build_flatvec()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_csv_column() {
+        let csv = "token,label\nhello,O\n\"a, \"\"quoted\"\"\ntoken\",B-MISC\r\n\"\",O";
+        let tokens = FlatStr::from_csv_column(csv.as_bytes(), b',', true, 0).unwrap();
+        assert_eq!(
+            vec!["hello", "a, \"quoted\"\ntoken", ""],
+            tokens.iter_strings().collect::<Vec<_>>()
+        );
+        let labels = FlatStr::from_csv_column(csv.as_bytes(), b',', false, 1).unwrap();
+        assert_eq!(
+            vec!["label", "O", "B-MISC", "O"],
+            labels.iter_strings().collect::<Vec<_>>()
+        );
+        let error = FlatStr::from_csv_column("a\tb\nc\n".as_bytes(), b'\t', false, 1).unwrap_err();
+        assert_eq!("record 2 has no column 1", error.to_string());
+        assert!(FlatStr::from_csv_column("\"open\n".as_bytes(), b',', false, 0).is_err());
+    }
+
+    #[test]
+    fn test_csv_grouped() {
+        let tsv = "sentence\tid\n1\t4\n1\t2\n2\t7\n3\t1\n3\t1\n";
+        let (keys, ids) =
+            FlatVec::from_csv_grouped(tsv.as_bytes(), b'\t', true, 0, 1, str::parse::<u32>)
+                .unwrap();
+        assert_eq!(vec!["1", "2", "3"], keys);
+        assert_eq!(FlatVec::new(vec![vec![4, 2], vec![7], vec![1, 1]]), ids);
+        let invalid = "1\tx\n".as_bytes();
+        let result = FlatVec::from_csv_grouped(invalid, b'\t', false, 0, 1, str::parse::<u32>);
+        assert_eq!(io::ErrorKind::InvalidData, result.unwrap_err().kind());
+    }
+}
@@ -0,0 +1,148 @@
+use crate::iterator::first_invalid_offset;
+use crate::offset::Offset;
+use crate::{FlatArray, FlatBuilder, FlatVec};
+use serde::de::Error;
+use serde::{Deserialize, Deserializer};
+
+// The collections are deserialized through these mirrors of their
+// layout, and their offsets are checked before they are built: the
+// iterators read the content without bounds checks.
+
+#[derive(Deserialize)]
+#[serde(rename = "FlatArray")]
+struct RawFlatArray<T, O> {
+    content: Box<[T]>,
+    indices: Box<[O]>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename = "FlatVec")]
+struct RawFlatVec<T, O> {
+    content: Vec<T>,
+    indices: Vec<O>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename = "FlatBuilder")]
+struct RawFlatBuilder<T> {
+    content: Vec<T>,
+    indices: Vec<usize>,
+}
+
+/// Checks that the offsets never decrease and stay inside the content,
+/// describing the first invalid offset otherwise.
+pub(crate) fn check_indices<O: Offset, E: Error>(
+    indices: &[O],
+    content_len: usize,
+) -> Result<(), E> {
+    let Some(i) = first_invalid_offset(indices, content_len) else {
+        return Ok(());
+    };
+    match indices.get(i + 1) {
+        Some(next) => Err(E::custom(format!(
+            "offsets must be non-decreasing, but offset {i} ({:?}) is greater than offset {} ({next:?})",
+            indices[i],
+            i + 1,
+        ))),
+        None => Err(E::custom(format!(
+            "last offset ({:?}) is past the end of the content (length {content_len})",
+            indices[i]
+        ))),
+    }
+}
+
+impl<'de, T, O> Deserialize<'de> for FlatArray<T, O>
+where
+    T: Deserialize<'de>,
+    O: Offset + Deserialize<'de>,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = RawFlatArray::deserialize(deserializer)?;
+        check_indices(&raw.indices, raw.content.len())?;
+        Ok(Self {
+            content: raw.content,
+            indices: raw.indices,
+        })
+    }
+}
+
+impl<'de, T, O> Deserialize<'de> for FlatVec<T, O>
+where
+    T: Deserialize<'de>,
+    O: Offset + Deserialize<'de>,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = RawFlatVec::deserialize(deserializer)?;
+        check_indices(&raw.indices, raw.content.len())?;
+        Ok(Self {
+            content: raw.content,
+            indices: raw.indices,
+        })
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for FlatBuilder<T> {
+    /// The builder pushes new sub-arrays after its last offset, which
+    /// must therefore exist and be the end of the content.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = RawFlatBuilder::deserialize(deserializer)?;
+        check_indices(&raw.indices, raw.content.len())?;
+        match raw.indices.last() {
+            Some(&last) if last == raw.content.len() => Ok(Self {
+                content: raw.content,
+                indices: raw.indices,
+            }),
+            _ => Err(D::Error::custom(format!(
+                "last offset must be the end of the content (length {})",
+                raw.content.len()
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde::de::IntoDeserializer;
+    use serde::de::value::{self, SeqDeserializer};
+
+    fn deserialize<'de, F: Deserialize<'de>>(
+        content: Vec<usize>,
+        indices: Vec<usize>,
+    ) -> Result<F, value::Error> {
+        let deserializer: SeqDeserializer<_, value::Error> =
+            vec![content, indices].into_deserializer();
+        F::deserialize(deserializer)
+    }
+
+    #[test]
+    fn test_valid_deserialization() {
+        let flat: FlatArray<usize> = deserialize(vec![4, 5, 6], vec![0, 1, 3]).unwrap();
+        assert_eq!(FlatArray::new(vec![vec![4], vec![5, 6]]), flat);
+    }
+
+    #[test]
+    fn test_invalid_deserialization() {
+        let error = deserialize::<FlatVec<usize>>(vec![4, 5, 6], vec![0, 2, 1]).unwrap_err();
+        assert!(error.to_string().contains("non-decreasing"));
+        let error = deserialize::<FlatArray<usize>>(vec![4, 5, 6], vec![0, 4]).unwrap_err();
+        assert!(error.to_string().contains("past the end"));
+        assert!(deserialize::<FlatBuilder<usize>>(vec![4, 5, 6], vec![]).is_err());
+        assert!(deserialize::<FlatBuilder<usize>>(vec![4, 5, 6], vec![0, 3]).is_ok());
+    }
+
+    #[test]
+    fn test_offset_type_deserialization() {
+        let deserializer: SeqDeserializer<_, value::Error> =
+            vec![vec![4u32, 5, 6], vec![0, 1, 3]].into_deserializer();
+        let flat = FlatVec::<u32, u32>::deserialize(deserializer).unwrap();
+        assert_eq!(
+            FlatVec::<u32, u32>::try_from_nested(vec![vec![4], vec![5, 6]]).unwrap(),
+            flat
+        );
+        let deserializer: SeqDeserializer<_, value::Error> =
+            vec![vec![4u16, 5, 6], vec![0, 4]].into_deserializer();
+        let error = FlatArray::<u16, u16>::deserialize(deserializer).unwrap_err();
+        assert!(error.to_string().contains("past the end"));
+    }
+}
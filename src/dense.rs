@@ -0,0 +1,182 @@
+//! Conversions between the flat collections and row-major dense
+//! buffers, e.g. to feed batches of sub-arrays to a model. They are
+//! written once over the content and the offsets, and exposed by both
+//! `FlatVec` and `FlatArray`.
+use crate::{FlatArray, FlatVec};
+
+/// Returns `true` if all the sub-arrays delimited by `indices` have the
+/// same length.
+fn is_rectangular(indices: &[usize]) -> bool {
+    let mut lengths = indices.windows(2).map(|w| w[1] - w[0]);
+    match lengths.next() {
+        None => true,
+        Some(first) => lengths.all(|l| l == first),
+    }
+}
+
+/// Copies the sub-arrays delimited by `indices` into a row-major dense
+/// buffer, along with the number of columns. Returns `None` if they do
+/// not all have the same length.
+fn to_dense<T: Clone>(content: &[T], indices: &[usize]) -> Option<(Vec<T>, usize)> {
+    if !is_rectangular(indices) {
+        return None;
+    }
+    let ncols = match *indices {
+        [first, second, ..] => second - first,
+        _ => 0,
+    };
+    let start = indices.first().copied().unwrap_or(0);
+    let end = indices.last().copied().unwrap_or(0);
+    Some((content[start..end].to_vec(), ncols))
+}
+
+/// Pads every sub-array delimited by `indices` with `pad_value` up to
+/// the length of the longest one. Returns the row-major padded buffer,
+/// its mask and the number of columns.
+fn pad_to_rectangular<T: Clone>(
+    content: &[T],
+    indices: &[usize],
+    pad_value: T,
+) -> (Vec<T>, Vec<bool>, usize) {
+    let ncols = indices.windows(2).map(|w| w[1] - w[0]).max().unwrap_or(0);
+    let nrows = indices.len().saturating_sub(1);
+    let mut data = Vec::with_capacity(nrows * ncols);
+    let mut mask = Vec::with_capacity(nrows * ncols);
+    for w in indices.windows(2) {
+        data.extend_from_slice(&content[w[0]..w[1]]);
+        data.resize(data.len() + ncols - (w[1] - w[0]), pad_value.clone());
+        mask.resize(mask.len() + w[1] - w[0], true);
+        mask.resize(mask.len() + ncols - (w[1] - w[0]), false);
+    }
+    (data, mask, ncols)
+}
+
+impl<T> FlatVec<T> {
+    /// Returns `true` if all the sub-arrays have the same length.
+    pub fn is_rectangular(&self) -> bool {
+        is_rectangular(&self.indices)
+    }
+    /// Copies the content into a row-major dense buffer, along with the
+    /// number of columns. Returns `None` if the `FlatVec` is not
+    /// rectangular.
+    pub fn to_dense(&self) -> Option<(Vec<T>, usize)>
+    where
+        T: Clone,
+    {
+        to_dense(&self.content, &self.indices)
+    }
+    /// Splits a row-major dense buffer into sub-arrays of `ncols`
+    /// elements. Returns `None` if `ncols` does not divide the length
+    /// of `data`, or is zero while `data` is not empty.
+    pub fn from_dense(data: Vec<T>, ncols: usize) -> Option<Self> {
+        let nrows = match ncols {
+            0 if data.is_empty() => 0,
+            0 => return None,
+            _ if !data.len().is_multiple_of(ncols) => return None,
+            _ => data.len() / ncols,
+        };
+        let indices: Vec<usize> = (0..=nrows).map(|i| i * ncols).collect();
+        Some(Self {
+            content: data,
+            indices,
+        })
+    }
+}
+
+impl<T: Clone> FlatVec<T> {
+    /// Pads every sub-array with `pad_value` up to the length of the
+    /// longest one. Returns the row-major padded buffer, a mask of the
+    /// same shape that is `true` for the original elements and `false`
+    /// for the padding, and the number of columns.
+    pub fn pad_to_rectangular(&self, pad_value: T) -> (Vec<T>, Vec<bool>, usize) {
+        pad_to_rectangular(&self.content, &self.indices, pad_value)
+    }
+    /// Inverse of `pad_to_rectangular`: keeps the first `lengths[i]`
+    /// elements of the i-th row of the padded buffer `data`. Returns
+    /// `None` if `data` cannot be split into `lengths.len()` rows or if
+    /// a length is larger than a row.
+    pub fn from_padded(data: &[T], lengths: &[usize]) -> Option<Self> {
+        let ncols = match lengths.len() {
+            0 if data.is_empty() => 0,
+            0 => return None,
+            nrows if !data.len().is_multiple_of(nrows) => return None,
+            nrows => data.len() / nrows,
+        };
+        let mut content = Vec::with_capacity(lengths.iter().sum());
+        let mut indices = Vec::with_capacity(lengths.len() + 1);
+        indices.push(0);
+        for (row, &length) in lengths.iter().enumerate() {
+            if length > ncols {
+                return None;
+            }
+            content.extend_from_slice(&data[row * ncols..row * ncols + length]);
+            indices.push(content.len());
+        }
+        Some(Self { content, indices })
+    }
+}
+
+impl<T> FlatArray<T> {
+    /// See `FlatVec::is_rectangular`.
+    pub fn is_rectangular(&self) -> bool {
+        is_rectangular(&self.indices)
+    }
+    /// See `FlatVec::to_dense`.
+    pub fn to_dense(&self) -> Option<(Vec<T>, usize)>
+    where
+        T: Clone,
+    {
+        to_dense(&self.content, &self.indices)
+    }
+    /// See `FlatVec::from_dense`.
+    pub fn from_dense(data: Vec<T>, ncols: usize) -> Option<Self> {
+        FlatVec::from_dense(data, ncols).map(FlatVec::into_flatarray)
+    }
+}
+
+impl<T: Clone> FlatArray<T> {
+    /// See `FlatVec::pad_to_rectangular`.
+    pub fn pad_to_rectangular(&self, pad_value: T) -> (Vec<T>, Vec<bool>, usize) {
+        pad_to_rectangular(&self.content, &self.indices, pad_value)
+    }
+    /// See `FlatVec::from_padded`.
+    pub fn from_padded(data: &[T], lengths: &[usize]) -> Option<Self> {
+        FlatVec::from_padded(data, lengths).map(FlatVec::into_flatarray)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_dense_conversion() {
+        let flat = FlatVec::new(vec![vec![1, 2], vec![3, 4], vec![5, 6]]);
+        assert!(flat.is_rectangular());
+        let (data, ncols) = flat.to_dense().unwrap();
+        assert_eq!((vec![1, 2, 3, 4, 5, 6], 2), (data.clone(), ncols));
+        let flat_array = FlatArray::from_dense(data.clone(), ncols).unwrap();
+        assert_eq!(Some((data.clone(), ncols)), flat_array.to_dense());
+        assert_eq!(Some(flat), FlatVec::from_dense(data, ncols));
+        let ragged = FlatArray::new(vec![vec![1, 2], vec![3]]);
+        assert!(!ragged.is_rectangular());
+        assert_eq!(None, ragged.to_dense());
+        assert_eq!(None, FlatVec::from_dense(vec![1, 2, 3], 2));
+        assert_eq!(None, FlatArray::from_dense(vec![1, 2, 3], 0));
+    }
+
+    #[test]
+    fn test_pad_to_rectangular() {
+        let flat = FlatArray::new(vec![vec![1, 2, 3], vec![], vec![4]]);
+        let (data, mask, ncols) = flat.pad_to_rectangular(0);
+        assert_eq!(3, ncols);
+        assert_eq!(vec![1, 2, 3, 0, 0, 0, 4, 0, 0], data);
+        let expected_mask = [true, true, true, false, false, false, true, false, false];
+        assert_eq!(expected_mask.to_vec(), mask);
+        let flat_vec = flat.clone().into_flatvec();
+        assert_eq!((data.clone(), mask, ncols), flat_vec.pad_to_rectangular(0));
+        assert_eq!(Some(flat_vec), FlatVec::from_padded(&data, &[3, 0, 1]));
+        assert_eq!(Some(flat), FlatArray::from_padded(&data, &[3, 0, 1]));
+        assert_eq!(None, FlatArray::from_padded(&data, &[4, 0, 1]));
+    }
+}
@@ -0,0 +1,46 @@
+use std::fmt;
+use std::str::Utf8Error;
+
+/// Errors reported by the checked operations of the crate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FlatError {
+    /// The offsets decrease or point past the end of the content.
+    InvalidIndices,
+    /// The string at `index` is not valid UTF-8.
+    InvalidUtf8 { index: usize, error: Utf8Error },
+    /// The string at `index` contains a NUL byte at `position`.
+    InteriorNul { index: usize, position: usize },
+    /// An iterator yielded `actual` elements after reporting `expected`.
+    LengthMismatch { expected: usize, actual: usize },
+    /// An offset does not fit in the offset type `O` of the collection.
+    OffsetOverflow,
+}
+
+impl fmt::Display for FlatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FlatError::InvalidIndices => {
+                f.write_str("offsets are decreasing or past the end of the content")
+            }
+            FlatError::InvalidUtf8 { index, error } => {
+                write!(f, "string {index} is not valid UTF-8: {error}")
+            }
+            FlatError::InteriorNul { index, position } => {
+                write!(f, "string {index} contains a NUL byte at {position}")
+            }
+            FlatError::LengthMismatch { expected, actual } => {
+                write!(f, "expected {expected} elements, got {actual}")
+            }
+            FlatError::OffsetOverflow => f.write_str("offset overflow"),
+        }
+    }
+}
+
+impl std::error::Error for FlatError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FlatError::InvalidUtf8 { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
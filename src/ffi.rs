@@ -0,0 +1,171 @@
+//! C interface to the flat collections. A collection built from C is
+//! an opaque `FlatArray` pointer, and its buffers are exposed through
+//! the `FlatArrayRaw` view. The functions are generated for a few
+//! element types, with a `flatarray_<type>_` prefix.
+use crate::{FlatArray, FlatView};
+use std::ptr;
+
+/// C view over the buffers of a flat collection. The i-th sub-array is
+/// made of the elements `ptr[offsets_ptr[i]..offsets_ptr[i + 1]]`.
+#[repr(C)]
+#[derive(Debug)]
+pub struct FlatArrayRaw<T> {
+    pub ptr: *const T,
+    pub len: usize,
+    pub offsets_ptr: *const usize,
+    pub offsets_len: usize,
+}
+
+impl<T> Clone for FlatArrayRaw<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for FlatArrayRaw<T> {}
+
+impl<T> FlatArrayRaw<T> {
+    /// Borrows the buffers described by the raw view. Returns `None` if
+    /// a pointer is null or if the offsets are not valid.
+    ///
+    /// # Safety
+    /// `ptr` and `offsets_ptr` must point to `len` and `offsets_len`
+    /// initialized values, which must outlive `'a` and must not be
+    /// mutated during `'a`.
+    pub unsafe fn as_view<'a>(&self) -> Option<FlatView<'a, T>> {
+        if self.ptr.is_null() || self.offsets_ptr.is_null() {
+            return None;
+        }
+        let content = unsafe { std::slice::from_raw_parts(self.ptr, self.len) };
+        let indices = unsafe { std::slice::from_raw_parts(self.offsets_ptr, self.offsets_len) };
+        FlatView::new(content, indices)
+    }
+}
+
+impl<T> FlatArray<T> {
+    /// Returns a raw view over the buffers of the `FlatArray`, valid as
+    /// long as the `FlatArray` is neither dropped nor mutated.
+    pub fn as_raw(&self) -> FlatArrayRaw<T> {
+        FlatArrayRaw {
+            ptr: self.content.as_ptr(),
+            len: self.content.len(),
+            offsets_ptr: self.indices.as_ptr(),
+            offsets_len: self.indices.len(),
+        }
+    }
+}
+
+macro_rules! ffi_functions {
+    ($t:ty, $new:ident, $view:ident, $free:ident) => {
+        /// Copies the given buffers into a new `FlatArray`. Returns a
+        /// null pointer if a pointer is null or if the offsets are not
+        /// valid. The result must be released with the matching `free`
+        /// function.
+        ///
+        /// # Safety
+        /// `ptr` and `offsets_ptr` must point to `len` and
+        /// `offsets_len` initialized values.
+        #[unsafe(no_mangle)]
+        pub unsafe extern "C" fn $new(
+            ptr: *const $t,
+            len: usize,
+            offsets_ptr: *const usize,
+            offsets_len: usize,
+        ) -> *mut FlatArray<$t> {
+            let raw = FlatArrayRaw {
+                ptr,
+                len,
+                offsets_ptr,
+                offsets_len,
+            };
+            match unsafe { raw.as_view() } {
+                Some(view) => {
+                    Box::into_raw(Box::new(FlatArray::from_raw(view.content, view.indices)))
+                }
+                None => ptr::null_mut(),
+            }
+        }
+
+        /// Returns a raw view over the buffers of `flat`. The view is
+        /// zeroed if `flat` is null.
+        ///
+        /// # Safety
+        /// `flat` must be null or a pointer returned by the matching
+        /// `new` function and not yet freed.
+        #[unsafe(no_mangle)]
+        pub unsafe extern "C" fn $view(flat: *const FlatArray<$t>) -> FlatArrayRaw<$t> {
+            match unsafe { flat.as_ref() } {
+                Some(flat) => flat.as_raw(),
+                None => FlatArrayRaw {
+                    ptr: ptr::null(),
+                    len: 0,
+                    offsets_ptr: ptr::null(),
+                    offsets_len: 0,
+                },
+            }
+        }
+
+        /// Releases a `FlatArray` built by the matching `new` function.
+        /// Does nothing if `flat` is null.
+        ///
+        /// # Safety
+        /// `flat` must be null or a pointer returned by the matching
+        /// `new` function and not yet freed.
+        #[unsafe(no_mangle)]
+        pub unsafe extern "C" fn $free(flat: *mut FlatArray<$t>) {
+            if !flat.is_null() {
+                drop(unsafe { Box::from_raw(flat) });
+            }
+        }
+    };
+}
+
+ffi_functions!(u8, flatarray_u8_new, flatarray_u8_view, flatarray_u8_free);
+ffi_functions!(
+    u32,
+    flatarray_u32_new,
+    flatarray_u32_view,
+    flatarray_u32_free
+);
+ffi_functions!(
+    i64,
+    flatarray_i64_new,
+    flatarray_i64_view,
+    flatarray_i64_free
+);
+ffi_functions!(
+    f32,
+    flatarray_f32_new,
+    flatarray_f32_view,
+    flatarray_f32_free
+);
+ffi_functions!(
+    f64,
+    flatarray_f64_new,
+    flatarray_f64_view,
+    flatarray_f64_free
+);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_ffi_roundtrip() {
+        let content = [1u32, 2, 3, 4];
+        let offsets = [0usize, 1, 4];
+        unsafe {
+            let flat = flatarray_u32_new(content.as_ptr(), 4, offsets.as_ptr(), 3);
+            assert!(!flat.is_null());
+            let raw = flatarray_u32_view(flat);
+            let view = raw.as_view().unwrap();
+            let arrays: Vec<_> = view.iter_arrays().collect();
+            let expected: Vec<&[u32]> = vec![&[1], &[2, 3, 4]];
+            assert_eq!(expected, arrays);
+            flatarray_u32_free(flat);
+            let invalid = [0usize, 5];
+            let flat = flatarray_u32_new(content.as_ptr(), 4, invalid.as_ptr(), 2);
+            assert!(flat.is_null());
+        }
+    }
+}
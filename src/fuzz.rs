@@ -0,0 +1,106 @@
+//! Fuzzing entry points, enabled by the `fuzz` feature. They take the
+//! raw bytes generated by a fuzzer (e.g. from a `cargo fuzz` target)
+//! and check that the iterators built on the unchecked accessors agree
+//! with a `Vec<Vec<u8>>` oracle, so that a bug in the offsets handling
+//! shows up as a panic instead of silent undefined behavior.
+//!
+//! No `arbitrary::Arbitrary` impls are provided: fuzz targets decode
+//! their input with `nested_from_bytes` instead.
+use crate::{FlatArray, FlatStr, FlatVec, FlatView, SharedFlatArray};
+
+/// Decodes `data` into sub-arrays: each sub-array is a length byte,
+/// taken modulo 16, followed by up to that many elements.
+pub fn nested_from_bytes(data: &[u8]) -> Vec<Vec<u8>> {
+    let mut nested = Vec::new();
+    let mut rest = data;
+    while let Some((&length, tail)) = rest.split_first() {
+        let (array, tail) = tail.split_at((length as usize % 16).min(tail.len()));
+        nested.push(array.to_vec());
+        rest = tail;
+    }
+    nested
+}
+
+/// Builds every owned collection from the sub-arrays decoded from
+/// `data`, and checks that iterating over them, mutably or not, yields
+/// the decoded sub-arrays.
+pub fn fuzz_iterators(data: &[u8]) {
+    let nested = nested_from_bytes(data);
+    let expected: Vec<&[u8]> = nested.iter().map(Vec::as_slice).collect();
+
+    let mut flat_vec = FlatVec::new(nested.clone());
+    assert!(flat_vec.iter_arrays().eq(expected.iter().copied()));
+    flat_vec
+        .iter_arrays_mut()
+        .for_each(|array| array.iter_mut().for_each(|x| *x = x.wrapping_add(1)));
+    let incremented: Vec<Vec<u8>> = nested
+        .iter()
+        .map(|array| array.iter().map(|x| x.wrapping_add(1)).collect())
+        .collect();
+    assert_eq!(FlatVec::new(incremented), flat_vec);
+
+    let flat_array = FlatArray::new(nested.clone());
+    assert!(flat_array.iter_arrays().eq(expected.iter().copied()));
+    let shared = SharedFlatArray::from(flat_array);
+    assert!(shared.iter_arrays().eq(expected.iter().copied()));
+
+    if let Ok(small) = FlatVec::<u8, u16>::try_from_nested(nested.clone()) {
+        assert!(small.iter_arrays().eq(expected.iter().copied()));
+    }
+}
+
+/// Interprets `data` as raw content and offsets, the first byte being
+/// the number of offsets. Checks that the validating constructors
+/// reject the offsets that the unchecked iterators cannot handle, and
+/// that the accepted ones are iterated like with checked slicing.
+pub fn fuzz_raw_offsets(data: &[u8]) {
+    let Some((&num_indices, rest)) = data.split_first() else {
+        return;
+    };
+    let (raw_indices, content) = rest.split_at((num_indices as usize).min(rest.len()));
+    let indices: Vec<usize> = raw_indices.iter().map(|&i| i as usize).collect();
+    let expected = || indices.windows(2).map(|w| &content[w[0]..w[1]]);
+
+    if let Some(view) = FlatView::new(content, &indices) {
+        assert!(view.iter_arrays().eq(expected()));
+    }
+    let flat_str = FlatStr::from_raw(content.to_vec(), indices.clone());
+    if flat_str.validate_utf8().is_ok() {
+        assert!(flat_str.iter_strings().map(str::as_bytes).eq(expected()));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Deterministic pseudo-random inputs, standing in for a fuzzer.
+    fn inputs() -> impl Iterator<Item = Vec<u8>> {
+        let mut state: u32 = 0x2545_f491;
+        (0..256).map(move |length| {
+            (0..length)
+                .map(|_| {
+                    state ^= state << 13;
+                    state ^= state >> 17;
+                    state ^= state << 5;
+                    state as u8
+                })
+                .collect()
+        })
+    }
+
+    #[test]
+    fn test_fuzz_iterators() {
+        assert_eq!(
+            vec![vec![7, 8], vec![], vec![9]],
+            nested_from_bytes(&[2, 7, 8, 16, 3, 9])
+        );
+        inputs().for_each(|data| fuzz_iterators(&data));
+    }
+
+    #[test]
+    fn test_fuzz_raw_offsets() {
+        fuzz_raw_offsets(&[3, 1, 1, 4, b'a', b'b', b'c', b'd']);
+        inputs().for_each(|data| fuzz_raw_offsets(&data));
+    }
+}
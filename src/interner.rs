@@ -0,0 +1,162 @@
+use crate::{FlatArray, FlatStr};
+use std::hash::{BuildHasher, RandomState};
+
+/// Marks an empty slot of the hash table.
+const EMPTY: u32 = u32::MAX;
+
+/// String interner storing each unique string once in a `FlatStr` and
+/// handing out stable `u32` ids, e.g. to manage the labels or the
+/// vocabulary of a corpus. The id of a string is its position in the
+/// `FlatStr`.
+#[derive(Debug, Clone)]
+pub struct FlatInterner {
+    strings: FlatStr,
+    /// Open-addressing hash table of ids, probed linearly. Its length is
+    /// a power of two, at least twice the number of strings.
+    table: Vec<u32>,
+    hasher: RandomState,
+}
+
+impl Default for FlatInterner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FlatInterner {
+    /// Creates an empty interner.
+    pub fn new() -> Self {
+        Self {
+            strings: FlatStr::new_empty(),
+            table: vec![EMPTY; 16],
+            hasher: RandomState::new(),
+        }
+    }
+    /// Number of interned strings.
+    pub fn len(&self) -> usize {
+        self.strings.indices.len() - 1
+    }
+    /// Returns `true` if no string was interned.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// Returns the id of `s`, interning it if it is new.
+    ///
+    /// # Panics
+    /// Panics if more than `u32::MAX - 1` strings are interned.
+    pub fn get_or_intern(&mut self, s: &str) -> u32 {
+        let slot = match self.find_slot(s) {
+            Ok(id) => return id,
+            Err(slot) => slot,
+        };
+        let id = u32::try_from(self.len())
+            .ok()
+            .filter(|&id| id != EMPTY)
+            .expect("too many interned strings");
+        self.strings.content.extend_from_slice(s.as_bytes());
+        self.strings.indices.push(self.strings.content.len());
+        self.table[slot] = id;
+        if 2 * self.len() > self.table.len() {
+            self.grow();
+        }
+        id
+    }
+    /// Returns the id of `s`, if it was interned.
+    pub fn get(&self, s: &str) -> Option<u32> {
+        self.find_slot(s).ok()
+    }
+    /// Returns the string with the given id.
+    ///
+    /// # Panics
+    /// Panics if `id` was not handed out by this interner.
+    pub fn resolve(&self, id: u32) -> &str {
+        self.try_resolve(id).expect("unknown id")
+    }
+    /// Returns the string with the given id, or `None` if `id` was not
+    /// handed out by this interner.
+    pub fn try_resolve(&self, id: u32) -> Option<&str> {
+        let id = id as usize;
+        let start = *self.strings.indices.get(id)?;
+        let end = *self.strings.indices.get(id + 1)?;
+        // SAFETY: only `&str`s are pushed in `strings`.
+        Some(unsafe { str::from_utf8_unchecked(&self.strings.content[start..end]) })
+    }
+    /// Returns an iterator over the interned strings, in id order.
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.strings.iter_strings()
+    }
+    /// Borrows the interned strings, the id of a string being its
+    /// position.
+    pub fn as_flatstr(&self) -> &FlatStr {
+        &self.strings
+    }
+    /// Consumes the interner, returning its strings as an immutable
+    /// `FlatArray<u8>`, the id of a string being its position.
+    pub fn freeze(self) -> FlatArray<u8> {
+        FlatArray::from(self.strings)
+    }
+    /// Returns `Ok(id)` if `s` is interned, or `Err(slot)` with the empty
+    /// slot where its id should go.
+    fn find_slot(&self, s: &str) -> Result<u32, usize> {
+        let mask = self.table.len() - 1;
+        let mut slot = self.hasher.hash_one(s) as usize & mask;
+        loop {
+            match self.table[slot] {
+                EMPTY => return Err(slot),
+                id if self.resolve(id) == s => return Ok(id),
+                _ => slot = (slot + 1) & mask,
+            }
+        }
+    }
+    /// Doubles the size of the hash table, reinserting every id.
+    fn grow(&mut self) {
+        let mut table = vec![EMPTY; 2 * self.table.len()];
+        let mask = table.len() - 1;
+        for (id, s) in self.iter().enumerate() {
+            let mut slot = self.hasher.hash_one(s) as usize & mask;
+            while table[slot] != EMPTY {
+                slot = (slot + 1) & mask;
+            }
+            table[slot] = id as u32;
+        }
+        self.table = table;
+    }
+}
+
+impl<S: AsRef<str>> FromIterator<S> for FlatInterner {
+    fn from_iter<I: IntoIterator<Item = S>>(iter: I) -> Self {
+        let mut interner = Self::new();
+        for s in iter {
+            interner.get_or_intern(s.as_ref());
+        }
+        interner
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_interner() {
+        let mut interner = FlatInterner::new();
+        let labels = ["O", "B-PER", "I-PER", "O", "B-LOC", "O", "B-PER"];
+        let ids: Vec<u32> = labels.iter().map(|l| interner.get_or_intern(l)).collect();
+        assert_eq!(vec![0, 1, 2, 0, 3, 0, 1], ids);
+        assert_eq!(4, interner.len());
+        assert_eq!(Some(2), interner.get("I-PER"));
+        assert_eq!(None, interner.get("I-LOC"));
+        assert_eq!("B-LOC", interner.resolve(3));
+        assert_eq!(None, interner.try_resolve(4));
+        let frozen = interner.freeze();
+        assert_eq!(b"B-LOC", frozen.iter_arrays().nth(3).unwrap());
+    }
+
+    #[test]
+    fn test_interner_grow() {
+        let interner: FlatInterner = (0..1000).chain(0..1000).map(|i| i.to_string()).collect();
+        assert_eq!(1000, interner.len());
+        assert!((0..1000).all(|i| interner.get(&i.to_string()) == Some(i)));
+        assert!(interner.iter().eq((0..1000).map(|i| i.to_string())));
+    }
+}
@@ -1,55 +1,224 @@
 use std::marker::PhantomData;
 use std::ops::Range;
 use std::ops::{Deref, DerefMut};
+use std::rc::Rc;
+use std::sync::Arc;
 
+use crate::offset::Offset;
 use crate::str::FlatStr;
 
+/// Returns the offset at `index`. Under the `strict` feature, the access
+/// is checked and panics with an informative message instead.
+///
+/// # Safety
+/// Without the `strict` feature, `index` must be inside the bounds of
+/// `indices`.
+#[inline(always)]
+pub(crate) unsafe fn get_offset<O: Offset>(indices: &[O], index: usize) -> usize {
+    #[cfg(feature = "strict")]
+    let offset = indices.get(index).unwrap_or_else(|| {
+        panic!(
+            "offset index {index} out of bounds for {} offsets",
+            indices.len()
+        )
+    });
+    #[cfg(not(feature = "strict"))]
+    let offset = unsafe { indices.get_unchecked(index) };
+    offset.to_usize()
+}
+
+/// Returns `content[range]`. Under the `strict` feature, the access is
+/// checked and panics with an informative message instead.
+///
+/// # Safety
+/// Without the `strict` feature, `range` must be inside the bounds of
+/// `content`.
+#[inline(always)]
+pub(crate) unsafe fn get_range<T>(content: &[T], range: Range<usize>) -> &[T] {
+    #[cfg(feature = "strict")]
+    {
+        let len = content.len();
+        content
+            .get(range.clone())
+            .unwrap_or_else(|| panic!("content range {range:?} out of bounds for {len} elements"))
+    }
+    #[cfg(not(feature = "strict"))]
+    unsafe {
+        content.get_unchecked(range)
+    }
+}
+
+/// Returns `&mut content[range]`. Under the `strict` feature, the access
+/// is checked and panics with an informative message instead.
+///
+/// # Safety
+/// Without the `strict` feature, `range` must be inside the bounds of
+/// `content`.
+#[inline(always)]
+pub(crate) unsafe fn get_range_mut<T>(content: &mut [T], range: Range<usize>) -> &mut [T] {
+    #[cfg(feature = "strict")]
+    {
+        let len = content.len();
+        content
+            .get_mut(range.clone())
+            .unwrap_or_else(|| panic!("content range {range:?} out of bounds for {len} elements"))
+    }
+    #[cfg(not(feature = "strict"))]
+    unsafe {
+        content.get_unchecked_mut(range)
+    }
+}
+
 pub trait FlattenedCollection<T> {
     fn indices_len(&self) -> usize;
+    /// Length of the buffer actually holding the content, which must not
+    /// be derived from the offsets: the checked accessors rely on it to
+    /// catch corrupted offsets.
+    fn content_len(&self) -> usize;
     /// # Safety
     /// The index must be inside the bounds of the indices.
     unsafe fn get_indices(&self, index: usize) -> usize;
     /// # Safety
     /// The range must be inside the bounds of the indices.
     unsafe fn get_content(&self, range: Range<usize>) -> &[T];
+    fn indices_empty(&self) -> bool {
+        self.indices_len() == 0
+    }
+    /// Returns the offset at `index`, or `None` if `index` is out of the
+    /// bounds of the indices.
+    fn try_get_indices(&self, index: usize) -> Option<usize> {
+        if index < self.indices_len() {
+            unsafe { Some(self.get_indices(index)) }
+        } else {
+            None
+        }
+    }
+    /// Returns the content in `range`, or `None` if `range` is
+    /// decreasing or goes past the end of the content.
+    fn try_get_content(&self, range: Range<usize>) -> Option<&[T]> {
+        if range.start <= range.end && range.end <= self.content_len() {
+            unsafe { Some(self.get_content(range)) }
+        } else {
+            None
+        }
+    }
+}
+
+/// Flattened collection owning its content, which can therefore be
+/// borrowed mutably.
+pub trait FlattenedCollectionMut<T>: FlattenedCollection<T> {
     /// # Safety
     /// The range must be inside the bounds of the indices.
     unsafe fn get_mut_content(&mut self, range: Range<usize>) -> &mut [T];
-    fn indices_empty(&self) -> bool {
-        self.indices_len() == 0
+    /// Returns the content in `range` mutably, or `None` if `range` is
+    /// decreasing or goes past the end of the content.
+    fn try_get_mut_content(&mut self, range: Range<usize>) -> Option<&mut [T]> {
+        if range.start <= range.end && range.end <= self.content_len() {
+            unsafe { Some(self.get_mut_content(range)) }
+        } else {
+            None
+        }
     }
 }
 
+/// Checks that `indices` describes valid sub-arrays of a content of
+/// length `content_len`: the offsets never decrease and the last one
+/// is inside the content.
+pub(crate) fn indices_are_valid<O: Offset>(indices: &[O], content_len: usize) -> bool {
+    first_invalid_offset(indices, content_len).is_none()
+}
+
+/// Returns the position of the first offset breaking the rules of
+/// `indices_are_valid`: an offset greater than the next one, or the
+/// last offset if it is past the end of the content.
+pub(crate) fn first_invalid_offset<O: Offset>(indices: &[O], content_len: usize) -> Option<usize> {
+    indices.windows(2).position(|w| w[0] > w[1]).or_else(|| {
+        let last = indices.len().checked_sub(1)?;
+        (indices[last].to_usize() > content_len).then_some(last)
+    })
+}
+
 impl<T> FlattenedCollection<T> for Box<dyn FlattenedCollection<T>> {
     fn indices_len(&self) -> usize {
         self.deref().indices_len()
     }
+    fn content_len(&self) -> usize {
+        self.deref().content_len()
+    }
     unsafe fn get_indices(&self, index: usize) -> usize {
         unsafe { self.deref().get_indices(index) }
     }
     unsafe fn get_content(&self, range: Range<usize>) -> &[T] {
         unsafe { self.deref().get_content(range) }
     }
-    unsafe fn get_mut_content(&mut self, range: Range<usize>) -> &mut [T] {
-        unsafe { self.deref_mut().get_mut_content(range) }
-    }
 }
 
 impl<T, Flat: FlattenedCollection<T>> FlattenedCollection<T> for Box<Flat> {
     fn indices_len(&self) -> usize {
         self.deref().indices_len()
     }
+    fn content_len(&self) -> usize {
+        self.deref().content_len()
+    }
     unsafe fn get_indices(&self, index: usize) -> usize {
         unsafe { self.deref().get_indices(index) }
     }
     unsafe fn get_content(&self, range: Range<usize>) -> &[T] {
         unsafe { self.deref().get_content(range) }
     }
+}
+
+impl<T, Flat: FlattenedCollectionMut<T>> FlattenedCollectionMut<T> for Box<Flat> {
     unsafe fn get_mut_content(&mut self, range: Range<usize>) -> &mut [T] {
         unsafe { self.deref_mut().get_mut_content(range) }
     }
 }
 
+impl<T, Flat: FlattenedCollection<T> + ?Sized> FlattenedCollection<T> for &Flat {
+    fn indices_len(&self) -> usize {
+        (**self).indices_len()
+    }
+    fn content_len(&self) -> usize {
+        (**self).content_len()
+    }
+    unsafe fn get_indices(&self, index: usize) -> usize {
+        unsafe { (**self).get_indices(index) }
+    }
+    unsafe fn get_content(&self, range: Range<usize>) -> &[T] {
+        unsafe { (**self).get_content(range) }
+    }
+}
+
+impl<T, Flat: FlattenedCollection<T> + ?Sized> FlattenedCollection<T> for Rc<Flat> {
+    fn indices_len(&self) -> usize {
+        self.deref().indices_len()
+    }
+    fn content_len(&self) -> usize {
+        self.deref().content_len()
+    }
+    unsafe fn get_indices(&self, index: usize) -> usize {
+        unsafe { self.deref().get_indices(index) }
+    }
+    unsafe fn get_content(&self, range: Range<usize>) -> &[T] {
+        unsafe { self.deref().get_content(range) }
+    }
+}
+
+impl<T, Flat: FlattenedCollection<T> + ?Sized> FlattenedCollection<T> for Arc<Flat> {
+    fn indices_len(&self) -> usize {
+        self.deref().indices_len()
+    }
+    fn content_len(&self) -> usize {
+        self.deref().content_len()
+    }
+    unsafe fn get_indices(&self, index: usize) -> usize {
+        unsafe { self.deref().get_indices(index) }
+    }
+    unsafe fn get_content(&self, range: Range<usize>) -> &[T] {
+        unsafe { self.deref().get_content(range) }
+    }
+}
+
 #[derive(Debug)]
 pub struct Iter<'a, Flat, T>
 where
@@ -101,55 +270,130 @@ where
         if self.token_vecs.indices_empty() || self.counter >= self.token_vecs.indices_len() - 1 {
             return None;
         }
-        let start = unsafe { self.token_vecs.get_indices(self.indice_index) };
-        let end = unsafe { self.token_vecs.get_indices(self.indice_index + 1) };
+        #[cfg(not(feature = "strict"))]
+        let array = {
+            let start = unsafe { self.token_vecs.get_indices(self.indice_index) };
+            let end = unsafe { self.token_vecs.get_indices(self.indice_index + 1) };
+            unsafe { self.token_vecs.get_content(start..end) }
+        };
+        #[cfg(feature = "strict")]
+        let array = {
+            let (start, end) = self
+                .token_vecs
+                .try_get_indices(self.indice_index)
+                .zip(self.token_vecs.try_get_indices(self.indice_index + 1))
+                .expect("indices out of bounds");
+            self.token_vecs
+                .try_get_content(start..end)
+                .unwrap_or_else(|| panic!("invalid content range {start}..{end}"))
+        };
         self.counter += 1;
         self.indice_index += 1;
-        unsafe { Some(self.token_vecs.get_content(start..end)) }
+        Some(array)
     }
 }
 
+/// Iterator over the sub-arrays of a flattened collection, yielding
+/// `&mut [T]`. It holds the offsets and the not-yet-yielded part of
+/// the content, and splits a sub-array off the front of the content at
+/// each step. The yielded slices are therefore disjoint by
+/// construction.
 #[derive(Debug)]
-pub struct IterMut<'a, Flat, T>
+pub struct IterMut<'a, Flat, T, O = usize>
 where
-    Flat: FlattenedCollection<T>,
+    Flat: FlattenedCollectionMut<T>,
 {
     indice_index: usize,
-    token_vecs: &'a mut Flat,
-    counter: usize,
-    phantom_data: PhantomData<&'a T>,
+    indices: &'a [O],
+    content: &'a mut [T],
+    phantom_data: PhantomData<&'a mut Flat>,
 }
 
-impl<'a, Flat, T> IterMut<'a, Flat, T>
+impl<'a, Flat, T, O: Offset> IterMut<'a, Flat, T, O>
 where
-    Flat: FlattenedCollection<T>,
+    Flat: FlattenedCollectionMut<T>,
 {
-    pub(crate) fn new(token_vecs: &'a mut Flat) -> Self {
+    /// Builds the iterator from the offsets and the content of the
+    /// collection. Out of bounds offsets make the iterator panic
+    /// instead of yielding aliased slices.
+    pub(crate) fn new(indices: &'a [O], content: &'a mut [T]) -> Self {
+        let start = indices.first().map_or(0, |o| o.to_usize());
         Self {
             indice_index: 0,
-            token_vecs,
-            counter: 0,
+            indices,
+            content: &mut content[start..],
             phantom_data: PhantomData,
         }
     }
 }
 
-impl<'a, Flat, T> Iterator for IterMut<'a, Flat, T>
+impl<'a, Flat, T, O: Offset> Iterator for IterMut<'a, Flat, T, O>
 where
-    Flat: FlattenedCollection<T>,
+    Flat: FlattenedCollectionMut<T>,
 {
     type Item = &'a mut [T];
     // NOTE: Inlining this function seems to reduce the performance
     // #[inline(always)]
     fn next(&mut self) -> Option<Self::Item> {
-        if self.counter >= self.token_vecs.indices_len() - 1 {
+        if self.indice_index + 1 >= self.indices.len() {
             return None;
         }
-        let start = unsafe { self.token_vecs.get_indices(self.indice_index) };
-        let end = unsafe { self.token_vecs.get_indices(self.indice_index + 1) };
-        self.counter += 1;
+        let start = self.indices[self.indice_index].to_usize();
+        let end = self.indices[self.indice_index + 1].to_usize();
+        let content = std::mem::take(&mut self.content);
+        let (array, rest) = content.split_at_mut(end - start);
+        self.content = rest;
         self.indice_index += 1;
-        unsafe { Some(&mut *(self.token_vecs.get_mut_content(start..end) as *mut _)) }
+        Some(array)
+    }
+}
+
+/// Iterator over the sub-arrays of a flattened collection, yielding each
+/// sub-array along with its index.
+#[derive(Debug)]
+pub struct EnumerateIter<'a, Flat, T>(pub(crate) Iter<'a, Flat, T>)
+where
+    Flat: FlattenedCollection<T>,
+    T: 'a;
+
+impl<'a, Flat, T> Clone for EnumerateIter<'a, Flat, T>
+where
+    Flat: FlattenedCollection<T>,
+    T: 'a,
+{
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<'a, Flat, T> Iterator for EnumerateIter<'a, Flat, T>
+where
+    Flat: FlattenedCollection<T>,
+{
+    type Item = (usize, &'a [T]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let index = self.0.indice_index;
+        self.0.next().map(|array| (index, array))
+    }
+}
+
+/// Iterator over the sub-arrays of a flattened collection, yielding each
+/// mutable sub-array along with its index.
+#[derive(Debug)]
+pub struct EnumerateIterMut<'a, Flat, T, O = usize>(pub(crate) IterMut<'a, Flat, T, O>)
+where
+    Flat: FlattenedCollectionMut<T>;
+
+impl<'a, Flat, T, O: Offset> Iterator for EnumerateIterMut<'a, Flat, T, O>
+where
+    Flat: FlattenedCollectionMut<T>,
+{
+    type Item = (usize, &'a mut [T]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let index = self.0.indice_index;
+        self.0.next().map(|array| (index, array))
     }
 }
 
@@ -161,7 +405,79 @@ impl<'a> Iterator for StrIter<'a> {
     fn next(&mut self) -> Option<Self::Item> {
         match self.0.next() {
             None => None,
+            #[cfg(not(feature = "strict"))]
             Some(bytes) => unsafe { Some(str::from_utf8_unchecked(bytes)) },
+            #[cfg(feature = "strict")]
+            Some(bytes) => Some(str::from_utf8(bytes).expect("invalid UTF-8 in FlatStr")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{FlattenedCollection, FlattenedCollectionMut};
+    use crate::{FlatArray, FlatCow, FlatVec};
+    use std::rc::Rc;
+
+    /// Holds every sub-array yielded by a mutable iterator at once and
+    /// writes through all of them.
+    fn write_through_all<'a>(arrays: impl Iterator<Item = &'a mut [u32]>) {
+        let arrays: Vec<&mut [u32]> = arrays.collect();
+        for (array_idx, array) in arrays.into_iter().enumerate().rev() {
+            for elem in array.iter_mut() {
+                *elem += 10 * array_idx as u32;
+            }
         }
     }
+
+    fn nested() -> Vec<Vec<u32>> {
+        vec![vec![1, 2], vec![], vec![3], vec![4, 5, 6]]
+    }
+
+    fn expected() -> FlatVec<u32> {
+        FlatVec::new(vec![vec![1, 2], vec![], vec![23], vec![34, 35, 36]])
+    }
+
+    #[test]
+    fn test_iter_mut_disjoint() {
+        let mut flat_vec = FlatVec::new(nested());
+        write_through_all(flat_vec.iter_arrays_mut());
+        assert_eq!(expected(), flat_vec);
+
+        let mut flat_array = FlatArray::new(nested());
+        write_through_all(flat_array.iter_arrays_mut());
+        assert_eq!(expected().into_flatarray(), flat_array);
+
+        let mut small = FlatVec::<u32, u16>::try_from_nested(nested()).unwrap();
+        write_through_all(small.iter_arrays_mut());
+        assert!(small.iter_arrays().eq(expected().iter_arrays()));
+
+        let original = FlatVec::new(nested());
+        let mut cow = FlatCow::from(&original);
+        write_through_all(cow.iter_arrays_mut());
+        assert_eq!(expected(), cow.into_owned());
+
+        let mut flat_vec = FlatVec::new(nested());
+        write_through_all(flat_vec.enumerate_arrays_mut().map(|(_, array)| array));
+        assert_eq!(expected(), flat_vec);
+    }
+
+    #[test]
+    fn test_iter_mut_offset_start() {
+        // The content before the first offset is never yielded.
+        let mut flat_vec = FlatVec::from_raw(vec![7, 1, 2, 3], vec![1, 3, 3, 4]);
+        write_through_all(flat_vec.iter_arrays_mut());
+        assert_eq!(vec![7, 1, 2, 23], flat_vec.content);
+    }
+
+    #[test]
+    fn test_try_get_mut_content() {
+        let mut boxed = Box::new(FlatVec::new(nested()));
+        boxed.try_get_mut_content(0..2).unwrap()[0] = 9;
+        assert_eq!(Some(&[9, 2][..]), boxed.try_get_content(0..2));
+        assert_eq!(None, boxed.try_get_mut_content(5..7));
+        // Shared collections only implement the read-only trait.
+        let rc = Rc::new(*boxed);
+        assert_eq!(Some(&[9, 2][..]), rc.try_get_content(0..2));
+    }
 }
@@ -0,0 +1,355 @@
+//! JSON Lines loaders, enabled by the `jsonl` feature. Every non-blank
+//! line of the input is a JSON object, from which a single field is
+//! extracted. The records are parsed by a small validating parser that
+//! only decodes the requested field and skips over the other values.
+use crate::{FlatArray, FlatBuilder, FlatStr};
+use std::io::{self, BufRead};
+
+/// Nesting depth of the skipped values above which a record is
+/// rejected, so that deeply nested input cannot overflow the stack.
+const MAX_DEPTH: usize = 128;
+
+struct Parser<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.peek() {
+            self.pos += 1;
+        }
+    }
+    fn expect(&mut self, byte: u8, message: &'static str) -> Result<(), &'static str> {
+        self.skip_whitespace();
+        if self.peek() != Some(byte) {
+            return Err(message);
+        }
+        self.pos += 1;
+        Ok(())
+    }
+    /// Skips the whitespace and consumes `byte` if it comes next.
+    fn eat(&mut self, byte: u8) -> bool {
+        self.skip_whitespace();
+        let found = self.peek() == Some(byte);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+    fn hex4(&mut self) -> Result<u32, &'static str> {
+        let digits = self
+            .bytes
+            .get(self.pos..self.pos + 4)
+            .and_then(|digits| std::str::from_utf8(digits).ok())
+            .and_then(|digits| u32::from_str_radix(digits, 16).ok())
+            .ok_or("invalid \\u escape")?;
+        self.pos += 4;
+        Ok(digits)
+    }
+    /// Parses a string, appending its unescaped bytes to `out`. The
+    /// input was checked to be UTF-8, so `out` stays valid UTF-8.
+    fn parse_string(&mut self, out: &mut Vec<u8>) -> Result<(), &'static str> {
+        self.expect(b'"', "expected a string")?;
+        loop {
+            let start = self.pos;
+            while let Some(byte) = self.peek() {
+                if byte == b'"' || byte == b'\\' || byte < 0x20 {
+                    break;
+                }
+                self.pos += 1;
+            }
+            out.extend_from_slice(&self.bytes[start..self.pos]);
+            match self.peek() {
+                Some(b'"') => {
+                    self.pos += 1;
+                    return Ok(());
+                }
+                Some(b'\\') => {
+                    self.pos += 1;
+                    let escaped = self.peek().ok_or("unterminated string")?;
+                    self.pos += 1;
+                    let c = match escaped {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => self.parse_unicode_escape()?,
+                        _ => return Err("invalid escape"),
+                    };
+                    out.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+                }
+                Some(_) => return Err("control character in a string"),
+                None => return Err("unterminated string"),
+            }
+        }
+    }
+    /// Parses the digits of a `\u` escape, and of the following one if
+    /// it is the second half of a surrogate pair.
+    fn parse_unicode_escape(&mut self) -> Result<char, &'static str> {
+        let high = self.hex4()?;
+        let code = if (0xd800..0xdc00).contains(&high) {
+            if self.bytes.get(self.pos..self.pos + 2) != Some(b"\\u") {
+                return Err("unpaired surrogate");
+            }
+            self.pos += 2;
+            let low = self.hex4()?;
+            if !(0xdc00..0xe000).contains(&low) {
+                return Err("unpaired surrogate");
+            }
+            0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00)
+        } else {
+            high
+        };
+        char::from_u32(code).ok_or("unpaired surrogate")
+    }
+    /// Skips any value, checking that it is valid JSON.
+    fn skip_value(&mut self, depth: usize) -> Result<(), &'static str> {
+        if depth > MAX_DEPTH {
+            return Err("value nested too deeply");
+        }
+        self.skip_whitespace();
+        match self.peek().ok_or("expected a value")? {
+            b'"' => self.parse_string(&mut Vec::new()),
+            b'{' => {
+                self.pos += 1;
+                if self.eat(b'}') {
+                    return Ok(());
+                }
+                loop {
+                    self.parse_string(&mut Vec::new())?;
+                    self.expect(b':', "expected ':'")?;
+                    self.skip_value(depth + 1)?;
+                    if !self.eat(b',') {
+                        return self.expect(b'}', "expected ',' or '}'");
+                    }
+                }
+            }
+            b'[' => {
+                self.pos += 1;
+                if self.eat(b']') {
+                    return Ok(());
+                }
+                loop {
+                    self.skip_value(depth + 1)?;
+                    if !self.eat(b',') {
+                        return self.expect(b']', "expected ',' or ']'");
+                    }
+                }
+            }
+            b'-' | b'0'..=b'9' => {
+                let start = self.pos;
+                while let Some(b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9') = self.peek() {
+                    self.pos += 1;
+                }
+                std::str::from_utf8(&self.bytes[start..self.pos])
+                    .unwrap()
+                    .parse::<f64>()
+                    .map(|_| ())
+                    .map_err(|_| "invalid number")
+            }
+            _ => {
+                for literal in [&b"true"[..], b"false", b"null"] {
+                    if self.bytes[self.pos..].starts_with(literal) {
+                        self.pos += literal.len();
+                        return Ok(());
+                    }
+                }
+                Err("expected a value")
+            }
+        }
+    }
+    /// Parses a whole record, calling `extract` on the value of its
+    /// first member named `field`. Returns `false` if there is none.
+    fn parse_record(
+        &mut self,
+        field: &str,
+        mut extract: impl FnMut(&mut Self) -> Result<(), &'static str>,
+    ) -> Result<bool, &'static str> {
+        self.expect(b'{', "a record must be a JSON object")?;
+        let mut found = false;
+        let mut key = Vec::new();
+        if !self.eat(b'}') {
+            loop {
+                key.clear();
+                self.parse_string(&mut key)?;
+                self.expect(b':', "expected ':'")?;
+                if !found && key == field.as_bytes() {
+                    extract(self)?;
+                    found = true;
+                } else {
+                    self.skip_value(1)?;
+                }
+                if !self.eat(b',') {
+                    self.expect(b'}', "expected ',' or '}'")?;
+                    break;
+                }
+            }
+        }
+        self.skip_whitespace();
+        if self.pos != self.bytes.len() {
+            return Err("trailing characters after the record");
+        }
+        Ok(found)
+    }
+}
+
+/// Calls `extract` on the value of `field` in every record of `reader`.
+fn for_each_record(
+    mut reader: impl BufRead,
+    field: &str,
+    mut extract: impl FnMut(&mut Parser) -> Result<(), &'static str>,
+) -> io::Result<()> {
+    let mut line = Vec::new();
+    let mut line_number = 0;
+    loop {
+        line.clear();
+        if reader.read_until(b'\n', &mut line)? == 0 {
+            return Ok(());
+        }
+        line_number += 1;
+        let invalid_data = |message: &str| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("line {line_number}: {message}"),
+            )
+        };
+        if std::str::from_utf8(&line).is_err() {
+            return Err(invalid_data("invalid UTF-8"));
+        }
+        let mut parser = Parser {
+            bytes: &line,
+            pos: 0,
+        };
+        parser.skip_whitespace();
+        if parser.pos == line.len() {
+            continue;
+        }
+        match parser.parse_record(field, &mut extract) {
+            Ok(true) => {}
+            Ok(false) => return Err(invalid_data(&format!("no field `{field}`"))),
+            Err(message) => return Err(invalid_data(message)),
+        }
+    }
+}
+
+impl FlatStr {
+    /// Reads JSON Lines from `reader` and collects the string `field` of
+    /// every record into a `FlatStr`, one string per record. Blank lines
+    /// are skipped, and if a record has the field more than once, the
+    /// first value is used. Fails with `io::ErrorKind::InvalidData`,
+    /// naming the line, if a record is not a valid JSON object or if its
+    /// field is missing or is not a string.
+    pub fn from_jsonl_reader(reader: impl BufRead, field: &str) -> io::Result<Self> {
+        let mut builder = FlatBuilder::default();
+        let mut string = Vec::new();
+        for_each_record(reader, field, |parser| {
+            string.clear();
+            parser.parse_string(&mut string)?;
+            builder.push(string.iter().copied());
+            Ok(())
+        })?;
+        Ok(builder.build_flatstr())
+    }
+}
+
+impl FlatArray<String> {
+    /// Reads JSON Lines from `reader` and collects the string array
+    /// `field` of every record into a sub-array, e.g. the tokens of each
+    /// sentence. See `FlatStr::from_jsonl_reader` for the blank lines
+    /// and the errors; a field that is not an array of strings also fails
+    /// with `io::ErrorKind::InvalidData`.
+    pub fn from_jsonl_reader(reader: impl BufRead, field: &str) -> io::Result<Self> {
+        let mut builder = FlatBuilder::default();
+        let mut strings = Vec::new();
+        for_each_record(reader, field, |parser| {
+            parser.expect(b'[', "expected an array of strings")?;
+            if !parser.eat(b']') {
+                loop {
+                    let mut string = Vec::new();
+                    parser.parse_string(&mut string)?;
+                    // The parser only produces UTF-8.
+                    strings.push(String::from_utf8(string).unwrap());
+                    if !parser.eat(b',') {
+                        parser.expect(b']', "expected ',' or ']'")?;
+                        break;
+                    }
+                }
+            }
+            builder.push(strings.drain(..));
+            Ok(())
+        })?;
+        Ok(builder.build_flatarray())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const JSONL: &str = r#"{"id": 1, "text": "hello \"world\"", "tokens": ["hello", "\"world\""]}
+{"meta": {"a": [1, -2.5e3, true, null, {}]}, "text": "café 😀\n", "tokens": []}
+
+  {"tokens": ["été"], "text": "", "text": "ignored"}
+"#;
+
+    #[test]
+    fn test_jsonl_strings() {
+        let texts = FlatStr::from_jsonl_reader(JSONL.as_bytes(), "text").unwrap();
+        assert_eq!(
+            vec!["hello \"world\"", "café 😀\n", ""],
+            texts.iter_strings().collect::<Vec<_>>()
+        );
+        let error = FlatStr::from_jsonl_reader(JSONL.as_bytes(), "meta").unwrap_err();
+        assert_eq!("line 1: no field `meta`", error.to_string());
+        let ids = "{\"id\": \"a\"}\n{\"id\": 2}\n";
+        let error = FlatStr::from_jsonl_reader(ids.as_bytes(), "id").unwrap_err();
+        assert_eq!("line 2: expected a string", error.to_string());
+    }
+
+    #[test]
+    fn test_jsonl_string_arrays() {
+        let tokens = FlatArray::<String>::from_jsonl_reader(JSONL.as_bytes(), "tokens").unwrap();
+        assert_eq!(
+            vec![vec!["hello", "\"world\""], vec![], vec!["été"]],
+            tokens
+                .iter_arrays()
+                .map(|array| array.iter().map(String::as_str).collect::<Vec<_>>())
+                .collect::<Vec<_>>()
+        );
+        let error = FlatArray::<String>::from_jsonl_reader(JSONL.as_bytes(), "text").unwrap_err();
+        assert_eq!(io::ErrorKind::InvalidData, error.kind());
+    }
+
+    #[test]
+    fn test_jsonl_invalid_records() {
+        for record in [
+            "[1, 2]",
+            r#"{"text": "a",}"#,
+            r#"{"text": "a"} {}"#,
+            r#"{"text": "\ud83d"}"#,
+            r#"{"text": "a", "n": 01x}"#,
+            r#"{"text": "unterminated}"#,
+            "{\"text\": \"tab\there\"}",
+        ] {
+            assert!(
+                FlatStr::from_jsonl_reader(record.as_bytes(), "text").is_err(),
+                "{record}"
+            );
+        }
+        let nested = format!(
+            r#"{{"a": {}{}, "text": "b"}}"#,
+            "[".repeat(200),
+            "]".repeat(200)
+        );
+        let error = FlatStr::from_jsonl_reader(nested.as_bytes(), "text").unwrap_err();
+        assert_eq!("line 1: value nested too deeply", error.to_string());
+    }
+}
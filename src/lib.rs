@@ -8,14 +8,83 @@
 /// `rusev`, which explains why its API surface is so small.
 mod array;
 pub use self::array::FlatArray;
+mod array2;
+pub use self::array2::{FlatArray2, OuterIter};
 mod vector;
 pub use self::vector::FlatVec;
 mod builder;
-pub use self::builder::FlatBuilder; // re-export the builder
+pub use self::builder::{FlatBuilder, try_collect}; // re-export the builder
 mod iterator;
-pub use self::iterator::{FlattenedCollection, Iter, IterMut};
+pub use self::iterator::{
+    EnumerateIter, EnumerateIterMut, FlattenedCollection, FlattenedCollectionMut, Iter, IterMut,
+};
 mod str;
-pub use self::str::FlatStr;
+pub use self::str::{Delimiter, FlatStr, FlatStrView};
+mod chain;
+pub use self::chain::FlatChain;
+mod cow;
+pub use self::cow::FlatCow;
+mod csr;
+pub use self::csr::CsrMatrix;
+mod map;
+pub use self::map::FlatMap;
+mod nested;
+pub use self::nested::{FlatNested, NestedSlice};
+mod shared;
+pub use self::shared::{SharedFlatArray, SharedIter, SharedSlice};
+mod uniform;
+pub use self::uniform::UniformFlatArray;
+mod view;
+pub use self::view::FlatView;
+mod pod;
+pub use self::pod::Pod;
+pub mod binary;
+#[cfg(feature = "serde")]
+mod de;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "jsonl")]
+mod jsonl;
+#[cfg(feature = "mmap")]
+mod mmap;
+#[cfg(feature = "mmap")]
+pub use self::mmap::MmapFlatArray;
+#[cfg(feature = "shm")]
+mod shm;
+#[cfg(feature = "shm")]
+pub use self::shm::ShmSegment;
+#[cfg(feature = "fuzz")]
+pub mod fuzz;
+#[cfg(feature = "serde")]
+pub mod serde_borrowed;
+#[cfg(feature = "serde")]
+pub mod serde_nested;
+mod stream;
+pub use self::stream::{FlatStreamReader, FlatStreamWriter, STREAM_MAGIC};
+pub mod conll;
+#[cfg(feature = "csv")]
+pub mod csv;
+mod error;
+pub use self::error::FlatError;
+mod interner;
+pub use self::interner::FlatInterner;
+mod cstr;
+pub use self::cstr::FlatCStr;
+mod spill;
+pub use self::spill::SpillingFlatBuilder;
+mod stats;
+pub use self::stats::LengthStats;
+mod offset;
+pub use self::offset::Offset;
+mod compact;
+pub use self::compact::CompactFlatArray;
+mod aligned;
+pub use self::aligned::AlignedFlatArray;
+mod small;
+pub use self::small::SmallFlatVec;
+mod dense;
+mod macros;
+mod per_array;
 
 #[cfg(test)]
 mod test {
@@ -60,4 +129,36 @@ mod test {
         let vectored: Vec<_> = Iter::new(&flat_vec).collect();
         assert!(expected.len() == vectored.len())
     }
+
+    #[test]
+    fn test_checked_accessors() {
+        let (flat_vec, _) = setup_flattened_iter("vec");
+        assert_eq!(flat_vec.try_get_indices(1), Some(5));
+        assert_eq!(flat_vec.try_get_indices(4), None);
+        assert_eq!(flat_vec.try_get_content(0..2), Some(&["this", "is"][..]));
+        assert_eq!(flat_vec.try_get_content(14..16), None);
+    }
+
+    #[test]
+    #[cfg(feature = "strict")]
+    #[should_panic(expected = "content range 2..9 out of bounds for 3 elements")]
+    fn test_strict_accessors() {
+        let flat_vec = FlatVec::from_raw(vec![1, 2, 3], vec![0, 2, 9]);
+        assert_eq!(2, unsafe { flat_vec.get_indices(1) });
+        unsafe { flat_vec.get_content(2..9) };
+    }
+
+    #[test]
+    fn test_shared_collections() {
+        let (flat_vec, expected) = setup_flattened_iter("vec");
+        let reference = &flat_vec;
+        let borrowed: Vec<_> = Iter::new(&reference).collect();
+        assert_eq!(expected, borrowed);
+        let rc = std::rc::Rc::new(flat_vec);
+        let counted: Vec<_> = Iter::new(&rc).collect();
+        assert_eq!(expected, counted);
+        let arc = std::sync::Arc::new(FlatVec::new(expected.clone()));
+        let shared: Vec<_> = Iter::new(&arc).collect();
+        assert_eq!(expected, shared);
+    }
 }
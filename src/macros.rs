@@ -0,0 +1,72 @@
+/// Builds a `FlatVec` from nested bracket syntax. The elements are
+/// written into a single content literal and the offsets are computed
+/// from the number of elements of each sub-array.
+///
+/// ```
+/// use flatarray::flat_vec;
+/// let flat_vec = flat_vec![[1, 2, 3], [4], [], [5, 6]];
+/// assert_eq!(4, flat_vec.iter_arrays().count());
+/// ```
+#[macro_export]
+macro_rules! flat_vec {
+    () => {
+        $crate::FlatVec::new_empty()
+    };
+    ($([$($x:expr),* $(,)?]),+ $(,)?) => {{
+        let lengths: &[usize] = &[$(<[()]>::len(&[$($crate::__flat_unit!($x)),*])),+];
+        let mut indices = ::std::vec::Vec::with_capacity(lengths.len() + 1);
+        indices.push(0usize);
+        for length in lengths {
+            indices.push(indices[indices.len() - 1] + length);
+        }
+        $crate::FlatVec::from_raw(::std::vec![$($($x,)*)+], indices)
+    }};
+}
+
+/// Builds a `FlatArray` from nested bracket syntax. See `flat_vec!`.
+///
+/// ```
+/// use flatarray::flat_array;
+/// let flat_array = flat_array![[1, 2, 3], [4], [5, 6]];
+/// assert_eq!(Some(&[4][..]), flat_array.iter_arrays().nth(1));
+/// ```
+#[macro_export]
+macro_rules! flat_array {
+    ($($arrays:tt)*) => {
+        $crate::FlatVec::into_flatarray($crate::flat_vec![$($arrays)*])
+    };
+}
+
+/// Replaces an element by `()`, to count the elements of a sub-array
+/// without evaluating them.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __flat_unit {
+    ($x:expr) => {
+        ()
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{FlatArray, FlatVec};
+
+    #[test]
+    fn test_flat_vec_macro() {
+        let flat_vec = flat_vec![[1, 2, 3], [4], [], [5, 6,],];
+        assert_eq!(
+            FlatVec::new(vec![vec![1, 2, 3], vec![4], vec![], vec![5, 6]]),
+            flat_vec
+        );
+        let empty: FlatVec<u8> = flat_vec![];
+        assert_eq!(FlatVec::new_empty(), empty);
+        let empty_arrays: FlatVec<u8> = flat_vec![[], []];
+        assert_eq!(vec![0, 0, 0], empty_arrays.indices);
+    }
+
+    #[test]
+    fn test_flat_array_macro() {
+        let flat_array = flat_array![["a", "b"], ["c"]];
+        assert_eq!(FlatArray::new(vec![vec!["a", "b"], vec!["c"]]), flat_array);
+    }
+}
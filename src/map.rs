@@ -0,0 +1,118 @@
+use crate::FlatVec;
+
+/// A `FlatVec<T>` whose sub-arrays are identified by a key, with a
+/// sorted index to look the sub-arrays up by key in `O(log n)`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlatMap<K, T> {
+    keys: Vec<K>,
+    /// Positions of the keys, sorted by key.
+    order: Vec<usize>,
+    values: FlatVec<T>,
+}
+
+impl<K: Ord, T> FlatMap<K, T> {
+    /// Pairs the i-th key with the i-th sub-array of `values`. Returns
+    /// `None` if there are not as many keys as sub-arrays. If a key is
+    /// repeated, lookups return its first sub-array.
+    pub fn new(keys: Vec<K>, values: FlatVec<T>) -> Option<Self> {
+        if keys.len() != values.indices.len().saturating_sub(1) {
+            return None;
+        }
+        let mut order: Vec<usize> = (0..keys.len()).collect();
+        order.sort_by(|&a, &b| keys[a].cmp(&keys[b]));
+        Some(Self {
+            keys,
+            order,
+            values,
+        })
+    }
+    /// Builds the map from `(key, value)` pairs sorted by key, grouping
+    /// the values of each run of equal keys. See `FlatVec::group_by_key`.
+    ///
+    /// # Panics
+    /// In debug builds, panics if the pairs are not sorted by key: the
+    /// lookups would silently miss keys otherwise.
+    pub fn from_sorted_pairs<I: IntoIterator<Item = (K, T)>>(iter: I) -> Self {
+        let (keys, values) = FlatVec::group_by_key(iter);
+        debug_assert!(
+            keys.windows(2).all(|w| w[0] < w[1]),
+            "the pairs are not sorted by key"
+        );
+        let order = (0..keys.len()).collect();
+        Self {
+            keys,
+            order,
+            values,
+        }
+    }
+    /// Returns the sub-array associated with `key`.
+    pub fn get(&self, key: &K) -> Option<&[T]> {
+        let position = self.order.partition_point(|&i| self.keys[i] < *key);
+        let &i = self.order.get(position)?;
+        (self.keys[i] == *key).then(|| self.array(i))
+    }
+    /// Returns `true` if the map has a sub-array for `key`.
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+}
+
+impl<K, T> FlatMap<K, T> {
+    /// Number of keys in the map.
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+    /// Returns `true` if the map has no keys.
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+    /// Borrows the keys, in insertion order.
+    pub fn keys(&self) -> &[K] {
+        &self.keys
+    }
+    /// Borrows the sub-arrays, in insertion order.
+    pub fn values(&self) -> &FlatVec<T> {
+        &self.values
+    }
+    /// Returns an iterator over the keys and their sub-array, in
+    /// insertion order.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &[T])> {
+        self.keys.iter().zip(self.values.iter_arrays())
+    }
+    /// Returns an iterator over the keys and their sub-array, in key
+    /// order.
+    pub fn iter_sorted(&self) -> impl Iterator<Item = (&K, &[T])> {
+        self.order.iter().map(|&i| (&self.keys[i], self.array(i)))
+    }
+    /// Splits the map back into its keys and sub-arrays.
+    pub fn into_parts(self) -> (Vec<K>, FlatVec<T>) {
+        (self.keys, self.values)
+    }
+    fn array(&self, i: usize) -> &[T] {
+        &self.values.content[self.values.indices[i]..self.values.indices[i + 1]]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_get_by_key() {
+        let values = FlatVec::new(vec![vec!["a", "b"], vec!["c"], vec![]]);
+        let map = FlatMap::new(vec!["doc-3", "doc-1", "doc-2"], values).unwrap();
+        assert_eq!(Some(&["c"][..]), map.get(&"doc-1"));
+        assert_eq!(Some(&["a", "b"][..]), map.get(&"doc-3"));
+        assert_eq!(Some(&[][..]), map.get(&"doc-2"));
+        assert_eq!(None, map.get(&"doc-4"));
+        let sorted: Vec<_> = map.iter_sorted().map(|(k, _)| *k).collect();
+        assert_eq!(vec!["doc-1", "doc-2", "doc-3"], sorted);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "the pairs are not sorted by key")]
+    fn test_from_unsorted_pairs() {
+        FlatMap::from_sorted_pairs([(2, 'a'), (1, 'b'), (2, 'c')]);
+    }
+}
@@ -0,0 +1,246 @@
+//! Read-only memory maps of collections written in the crate's binary
+//! format (see the `binary` module), enabled by the `mmap` feature. The
+//! format stores little-endian `u64` offsets right after a 24-byte
+//! header, followed by the elements, so on 64-bit little-endian targets
+//! the mapped bytes are used in place, without decoding or copying.
+//! The mapping is done with the `mmap` function of the C library, which
+//! the standard library already links on Unix.
+#[cfg(not(all(unix, target_endian = "little", target_pointer_width = "64")))]
+compile_error!("the mmap feature needs a 64-bit little-endian Unix target");
+
+use crate::binary::{Element, read_header};
+use crate::iterator::{Iter, indices_are_valid};
+use crate::pod::{Pod, cast_bytes};
+use crate::{FlatArray, FlatView};
+use std::ffi::{c_int, c_void};
+use std::fs::File;
+use std::io;
+use std::os::unix::io::AsRawFd;
+use std::path::Path;
+
+/// Length of the header of the binary format.
+pub(crate) const HEADER_LEN: usize = 24;
+
+const PROT_READ: c_int = 1;
+#[cfg(feature = "shm")]
+const PROT_WRITE: c_int = 2;
+const MAP_SHARED: c_int = 1;
+
+unsafe extern "C" {
+    fn mmap(
+        addr: *mut c_void,
+        len: usize,
+        prot: c_int,
+        flags: c_int,
+        fd: c_int,
+        offset: i64,
+    ) -> *mut c_void;
+    fn munmap(addr: *mut c_void, len: usize) -> c_int;
+}
+
+/// Read-only mapping of a whole file, unmapped on drop.
+#[derive(Debug)]
+pub(crate) struct Mapping {
+    ptr: *mut c_void,
+    len: usize,
+}
+
+// SAFETY: the mapping is owned by this value only, and only written
+// through `&mut self`.
+unsafe impl Send for Mapping {}
+unsafe impl Sync for Mapping {}
+
+impl Mapping {
+    /// Maps the whole `file`, which must not be empty, read-only.
+    ///
+    /// # Safety
+    /// The file must not be modified or truncated while it is mapped.
+    pub(crate) unsafe fn of_file(file: &File) -> io::Result<Self> {
+        unsafe { Self::new(file, PROT_READ) }
+    }
+    /// Maps the whole `file`, which must not be empty and must be open
+    /// for reading and writing, so that it can be written through
+    /// `bytes_mut`.
+    ///
+    /// # Safety
+    /// The file must not be modified or truncated while it is mapped.
+    #[cfg(feature = "shm")]
+    pub(crate) unsafe fn of_file_mut(file: &File) -> io::Result<Self> {
+        unsafe { Self::new(file, PROT_READ | PROT_WRITE) }
+    }
+    unsafe fn new(file: &File, prot: c_int) -> io::Result<Self> {
+        let len = usize::try_from(file.metadata()?.len())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "file too large to map"))?;
+        if len == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "cannot map an empty file",
+            ));
+        }
+        let fd = file.as_raw_fd();
+        // SAFETY: a new mapping does not alias any Rust memory.
+        let ptr = unsafe { mmap(std::ptr::null_mut(), len, prot, MAP_SHARED, fd, 0) };
+        if ptr as usize == usize::MAX {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Self { ptr, len })
+    }
+    pub(crate) fn bytes(&self) -> &[u8] {
+        // SAFETY: the mapping is `len` bytes long and lives as long as
+        // `self`.
+        unsafe { std::slice::from_raw_parts(self.ptr.cast(), self.len) }
+    }
+    /// Borrows the mapped bytes mutably. The mapping must have been
+    /// created by `of_file_mut`.
+    #[cfg(feature = "shm")]
+    pub(crate) fn bytes_mut(&mut self) -> &mut [u8] {
+        // SAFETY: as in `bytes`, and the borrow of `self` is exclusive.
+        unsafe { std::slice::from_raw_parts_mut(self.ptr.cast(), self.len) }
+    }
+}
+
+impl Drop for Mapping {
+    fn drop(&mut self) {
+        // SAFETY: the mapping was created by `mmap` with this length and
+        // no borrow of it outlives `self`.
+        unsafe { munmap(self.ptr, self.len) };
+    }
+}
+
+/// Read-only `FlatArray` backed by a memory-mapped file in the crate's
+/// binary format. The pages are loaded by the OS on access, so opening
+/// even a huge collection is immediate. Built with
+/// `FlatArray::open_mmap`.
+#[derive(Debug)]
+pub struct MmapFlatArray<T: 'static> {
+    /// View over `map`: it never outlives it, since it is only lent out
+    /// with the lifetime of `self`.
+    view: FlatView<'static, T>,
+    map: Mapping,
+}
+
+impl<T: Element + Pod> MmapFlatArray<T> {
+    /// Maps `file` and checks its header, its length and its offsets.
+    ///
+    /// # Safety
+    /// The file must not be modified or truncated while it is mapped.
+    pub(crate) unsafe fn from_file(file: &File) -> io::Result<Self> {
+        let invalid_data = |message| io::Error::new(io::ErrorKind::InvalidData, message);
+        let map = unsafe { Mapping::of_file(file)? };
+        let bytes = map.bytes();
+        let (indices_len, content_len) = read_header::<T>(&mut &bytes[..])?;
+        let content_start = indices_len
+            .checked_mul(size_of::<u64>())
+            .and_then(|len| len.checked_add(HEADER_LEN))
+            .ok_or_else(|| invalid_data("file length does not match the header"))?;
+        let expected_len = content_len
+            .checked_mul(T::SIZE)
+            .and_then(|len| len.checked_add(content_start));
+        if expected_len != Some(bytes.len()) {
+            return Err(invalid_data("file length does not match the header"));
+        }
+        let indices: &[usize] = cast_bytes(&bytes[HEADER_LEN..content_start])
+            .ok_or_else(|| invalid_data("misaligned offsets"))?;
+        let content: &[T] = cast_bytes(&bytes[content_start..])
+            .ok_or_else(|| invalid_data("misaligned content"))?;
+        if !indices_are_valid(indices, content.len()) {
+            return Err(invalid_data(
+                "offsets are decreasing or past the end of the content",
+            ));
+        }
+        // SAFETY: the slices point into the mapping, which is moved along
+        // with the view and unmapped only when both are dropped.
+        let view = unsafe {
+            FlatView::new_unchecked(
+                std::slice::from_raw_parts(content.as_ptr(), content.len()),
+                std::slice::from_raw_parts(indices.as_ptr(), indices.len()),
+            )
+        };
+        Ok(Self { view, map })
+    }
+}
+
+impl<T> MmapFlatArray<T> {
+    /// Borrows the mapped collection as a `FlatView`.
+    pub fn as_view(&self) -> FlatView<'_, T> {
+        self.view
+    }
+    /// Borrows the content of the mapped collection as a slice.
+    pub fn get_content(&self) -> &[T] {
+        self.view.get_content()
+    }
+    /// Returns an iterator over the arrays of the mapped collection.
+    /// The iterator will return a slice of type `&[T]`.
+    pub fn iter_arrays(&self) -> Iter<'_, FlatView<'_, T>, T> {
+        Iter::new(&self.view)
+    }
+    /// Size, in bytes, of the mapped file.
+    pub fn mapped_len(&self) -> usize {
+        self.map.len
+    }
+}
+
+impl<T: Element + Pod> FlatArray<T> {
+    /// Maps the file at `path`, written in the crate's binary format
+    /// (e.g. with `FlatArray::to_writer`), and exposes its sub-arrays
+    /// without reading or copying them. Fails with
+    /// `io::ErrorKind::InvalidData` if the file is not a valid
+    /// collection of `T`.
+    ///
+    /// # Safety
+    /// The file must not be modified or truncated while the returned
+    /// collection is alive, e.g. by another process: the mapped bytes
+    /// would change under the borrowed slices.
+    pub unsafe fn open_mmap(path: impl AsRef<Path>) -> io::Result<MmapFlatArray<T>> {
+        let file = File::open(path)?;
+        unsafe { MmapFlatArray::from_file(&file) }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::FlatVec;
+    use std::io::Write;
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("flatarray-mmap-{}-{name}", std::process::id()))
+    }
+
+    #[test]
+    fn test_open_mmap() {
+        let path = temp_path("roundtrip");
+        let flat = FlatVec::new(vec![vec![1.5f32, -2.0], vec![], vec![3.25]]);
+        flat.to_writer(File::create(&path).unwrap()).unwrap();
+        let mapped = unsafe { FlatArray::<f32>::open_mmap(&path) }.unwrap();
+        assert!(mapped.iter_arrays().eq(flat.iter_arrays()));
+        assert_eq!(&[1.5, -2.0, 3.25], mapped.get_content());
+        assert_eq!(24 + 4 * 8 + 3 * 4, mapped.mapped_len());
+        let error = unsafe { FlatArray::<u32>::open_mmap(&path) }.unwrap_err();
+        assert_eq!(io::ErrorKind::InvalidData, error.kind());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_open_mmap_invalid() {
+        let path = temp_path("invalid");
+        let mut bytes = Vec::new();
+        FlatVec::new(vec![vec![1u8, 2], vec![3]])
+            .to_writer(&mut bytes)
+            .unwrap();
+        File::create(&path)
+            .unwrap()
+            .write_all(&bytes[..bytes.len() - 1])
+            .unwrap();
+        let error = unsafe { FlatArray::<u8>::open_mmap(&path) }.unwrap_err();
+        assert_eq!(io::ErrorKind::InvalidData, error.kind());
+        // Second offset greater than the third one.
+        bytes[32] = 4;
+        File::create(&path).unwrap().write_all(&bytes).unwrap();
+        let error = unsafe { FlatArray::<u8>::open_mmap(&path) }.unwrap_err();
+        assert_eq!(io::ErrorKind::InvalidData, error.kind());
+        File::create(&path).unwrap();
+        assert!(unsafe { FlatArray::<u8>::open_mmap(&path) }.is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
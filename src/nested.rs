@@ -0,0 +1,276 @@
+use crate::FlatArray2;
+use crate::iterator::{
+    FlattenedCollection, FlattenedCollectionMut, Iter, get_offset, get_range, get_range_mut,
+};
+use std::ops::{Deref, Range};
+
+/// Flat collection with `DEPTH` levels of nesting: a `FlatNested<T, 1>`
+/// mimics a `Vec<Vec<T>>`, a `FlatNested<T, 2>` a `Vec<Vec<Vec<T>>>`,
+/// and so on. The content is stored in a single buffer and each level
+/// has its own offsets buffer: `indices[DEPTH - 1]` splits the content
+/// into the innermost arrays, and `indices[level]` groups the arrays of
+/// `level + 1`.
+#[derive(Debug, Eq, PartialEq, PartialOrd, Ord, Hash, Clone)]
+pub struct FlatNested<T, const DEPTH: usize> {
+    pub(crate) content: Box<[T]>,
+    pub(crate) indices: [Box<[usize]>; DEPTH],
+}
+
+impl<T, const DEPTH: usize> FlattenedCollection<T> for FlatNested<T, DEPTH> {
+    fn indices_len(&self) -> usize {
+        self.indices.last().map_or(0, |i| i.len())
+    }
+    fn content_len(&self) -> usize {
+        self.content.len()
+    }
+    unsafe fn get_indices(&self, index: usize) -> usize {
+        unsafe { get_offset(&self.indices[DEPTH - 1], index) }
+    }
+    unsafe fn get_content(&self, range: Range<usize>) -> &[T] {
+        unsafe { get_range(&self.content, range) }
+    }
+}
+
+impl<T, const DEPTH: usize> FlattenedCollectionMut<T> for FlatNested<T, DEPTH> {
+    unsafe fn get_mut_content(&mut self, range: Range<usize>) -> &mut [T] {
+        unsafe { get_range_mut(&mut self.content, range) }
+    }
+}
+
+impl<T, const DEPTH: usize> Default for FlatNested<T, DEPTH> {
+    fn default() -> Self {
+        Self {
+            content: Box::new([]),
+            indices: std::array::from_fn(|_| Box::new([0usize]) as Box<[usize]>),
+        }
+    }
+}
+
+impl<T, const DEPTH: usize> FlatNested<T, DEPTH> {
+    /// Number of nesting levels.
+    pub fn depth(&self) -> usize {
+        DEPTH
+    }
+    /// Number of arrays at `level`, `0` being the outermost level.
+    ///
+    /// # Panics
+    /// Panics if `level >= DEPTH`.
+    pub fn num_arrays(&self, level: usize) -> usize {
+        self.indices[level].len().saturating_sub(1)
+    }
+    /// Borrows the content of the FlatNested as a slice.
+    pub fn get_content(&self) -> &[T] {
+        self.content.as_ref()
+    }
+}
+
+impl<'a, T, const DEPTH: usize> FlatNested<T, DEPTH> {
+    /// Returns an iterator over the content. This iterator returns the
+    /// individual elements.
+    pub fn iter(&'a self) -> std::slice::Iter<'a, T> {
+        self.content.iter()
+    }
+    /// Returns an iterator over the outermost arrays. Each of them is a
+    /// `NestedSlice` that can be walked down to the content.
+    pub fn iter_outer(&'a self) -> impl Iterator<Item = NestedSlice<'a, T>> {
+        (0..self.num_arrays(0)).map(|index| NestedSlice {
+            content: &self.content,
+            levels: &self.indices,
+            level: 0,
+            index,
+        })
+    }
+    /// Returns an iterator over the innermost arrays, regardless of the
+    /// arrays they belong to. The iterator will return a slice of type
+    /// `&[T]`.
+    pub fn iter_arrays(&'a self) -> Iter<'a, FlatNested<T, DEPTH>, T> {
+        Iter::new(self)
+    }
+}
+
+impl<T, const DEPTH: usize> Deref for FlatNested<T, DEPTH> {
+    type Target = [T];
+    fn deref(&self) -> &Self::Target {
+        self.content.as_ref()
+    }
+}
+
+/// A single array of a `FlatNested`, at any nesting level.
+#[derive(Debug)]
+pub struct NestedSlice<'a, T> {
+    content: &'a [T],
+    levels: &'a [Box<[usize]>],
+    level: usize,
+    index: usize,
+}
+
+impl<T> Clone for NestedSlice<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for NestedSlice<'_, T> {}
+
+impl<'a, T> NestedSlice<'a, T> {
+    /// Nesting level of the array, `0` being the outermost level.
+    pub fn level(&self) -> usize {
+        self.level
+    }
+    /// Returns `true` if the array is at the innermost level, i.e. if
+    /// its children are elements rather than arrays.
+    pub fn is_innermost(&self) -> bool {
+        self.level + 1 == self.levels.len()
+    }
+    /// Number of children of the array.
+    pub fn len(&self) -> usize {
+        let range = self.children_range();
+        range.end - range.start
+    }
+    /// Returns `true` if the array has no children.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// Returns an iterator over the arrays nested in this one. The
+    /// iterator is empty if the array is at the innermost level.
+    pub fn iter_children(&self) -> impl Iterator<Item = NestedSlice<'a, T>> + use<'a, T> {
+        let range = if self.is_innermost() {
+            0..0
+        } else {
+            self.children_range()
+        };
+        let (content, levels, level) = (self.content, self.levels, self.level + 1);
+        range.map(move |index| NestedSlice {
+            content,
+            levels,
+            level,
+            index,
+        })
+    }
+    /// Borrows all the elements contained in the array, at any depth.
+    pub fn as_slice(&self) -> &'a [T] {
+        let (mut start, mut end) = (self.index, self.index + 1);
+        for offsets in &self.levels[self.level..] {
+            (start, end) = (offsets[start], offsets[end]);
+        }
+        &self.content[start..end]
+    }
+
+    fn children_range(&self) -> Range<usize> {
+        let offsets = &self.levels[self.level];
+        offsets[self.index]..offsets[self.index + 1]
+    }
+}
+
+impl<T> Deref for NestedSlice<'_, T> {
+    type Target = [T];
+    fn deref(&self) -> &Self::Target {
+        self.as_slice()
+    }
+}
+
+impl<T> From<Vec<Vec<T>>> for FlatNested<T, 1> {
+    fn from(value: Vec<Vec<T>>) -> Self {
+        let mut content = Vec::new();
+        let mut indices = vec![0];
+        for inner in value {
+            content.extend(inner);
+            indices.push(content.len());
+        }
+        Self {
+            content: content.into_boxed_slice(),
+            indices: [indices.into_boxed_slice()],
+        }
+    }
+}
+
+impl<T> From<Vec<Vec<Vec<T>>>> for FlatNested<T, 2> {
+    fn from(value: Vec<Vec<Vec<T>>>) -> Self {
+        FlatArray2::from(value).into()
+    }
+}
+
+impl<T> From<Vec<Vec<Vec<Vec<T>>>>> for FlatNested<T, 3> {
+    fn from(value: Vec<Vec<Vec<Vec<T>>>>) -> Self {
+        let mut outer_indices = vec![0];
+        let mut flattened = Vec::new();
+        for outer in value {
+            outer_indices.push(outer_indices.last().unwrap() + outer.len());
+            flattened.extend(outer);
+        }
+        let FlatNested {
+            content,
+            indices: [middle_indices, inner_indices],
+        } = FlatNested::<T, 2>::from(flattened);
+        Self {
+            content,
+            indices: [
+                outer_indices.into_boxed_slice(),
+                middle_indices,
+                inner_indices,
+            ],
+        }
+    }
+}
+
+impl<T> From<FlatArray2<T>> for FlatNested<T, 2> {
+    fn from(value: FlatArray2<T>) -> Self {
+        Self {
+            content: value.content,
+            indices: [value.outer_indices, value.inner_indices],
+        }
+    }
+}
+
+impl<T> From<FlatNested<T, 2>> for FlatArray2<T> {
+    fn from(value: FlatNested<T, 2>) -> Self {
+        let [outer_indices, inner_indices] = value.indices;
+        Self {
+            content: value.content,
+            inner_indices,
+            outer_indices,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_iter_nested() {
+        let corpus = vec![
+            vec![vec![vec![1, 2], vec![3]], vec![vec![4]]],
+            vec![vec![vec![5, 6]]],
+        ];
+        let nested = FlatNested::<_, 3>::from(corpus);
+        assert_eq!(3, nested.depth());
+        assert_eq!([2, 3, 4], [0, 1, 2].map(|l| nested.num_arrays(l)));
+        let inner: Vec<_> = nested.iter_arrays().collect();
+        let expected: Vec<&[i32]> = vec![&[1, 2], &[3], &[4], &[5, 6]];
+        assert_eq!(expected, inner);
+        let first = nested.iter_outer().next().unwrap();
+        assert_eq!(&[1, 2, 3, 4], first.as_slice());
+        let middle: Vec<_> = first.iter_children().map(|c| c.as_slice()).collect();
+        let expected: Vec<&[i32]> = vec![&[1, 2, 3], &[4]];
+        assert_eq!(expected, middle);
+        let leaf = first
+            .iter_children()
+            .next()
+            .unwrap()
+            .iter_children()
+            .nth(1)
+            .unwrap();
+        assert!(leaf.is_innermost());
+        assert_eq!(&[3], &leaf[..]);
+        assert_eq!(0, leaf.iter_children().count());
+    }
+
+    #[test]
+    fn test_flatarray2_conversion() {
+        let flat = FlatArray2::new(vec![vec![vec![1], vec![2, 3]], vec![]]);
+        let nested = FlatNested::from(flat.clone());
+        assert_eq!(2, nested.iter_outer().count());
+        assert_eq!(flat, FlatArray2::from(nested));
+    }
+}
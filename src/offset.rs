@@ -0,0 +1,59 @@
+use crate::error::FlatError;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+/// Integer type used to store the offsets of a `FlatVec` or a
+/// `FlatArray`. Smaller types than the default `usize` cut the memory
+/// taken by the offsets, e.g. `u32` for collections of less than 4
+/// billion elements.
+pub trait Offset: Copy + Ord + Hash + Debug + Default + 'static {
+    /// Converts the offset to a `usize`, panicking instead of truncating
+    /// it if it does not fit, e.g. a `u64` on a 32-bit target.
+    fn to_usize(self) -> usize;
+    /// Converts `value` to an offset, or returns `None` if it does not
+    /// fit.
+    fn from_usize(value: usize) -> Option<Self>;
+}
+
+macro_rules! impl_offset {
+    ($($t:ty),*) => {
+        $(
+            impl Offset for $t {
+                #[inline(always)]
+                fn to_usize(self) -> usize {
+                    usize::try_from(self).expect("offset does not fit in a usize")
+                }
+                #[inline(always)]
+                fn from_usize(value: usize) -> Option<Self> {
+                    <$t>::try_from(value).ok()
+                }
+            }
+        )*
+    };
+}
+
+impl_offset!(u16, u32, u64, usize);
+
+/// Converts `usize` offsets into offsets of type `O`, failing with
+/// `FlatError::OffsetOverflow` if one of them does not fit.
+pub(crate) fn convert_offsets<O: Offset>(indices: &[usize]) -> Result<Vec<O>, FlatError> {
+    indices
+        .iter()
+        .map(|&offset| O::from_usize(offset).ok_or(FlatError::OffsetOverflow))
+        .collect()
+}
+
+/// Flattens `vecs` into a content and its offsets of type `O`, failing
+/// with `FlatError::OffsetOverflow` as soon as an offset does not fit.
+pub(crate) fn flatten_nested<T, O: Offset>(
+    vecs: Vec<Vec<T>>,
+) -> Result<(Vec<T>, Vec<O>), FlatError> {
+    let mut content = Vec::with_capacity(vecs.iter().map(Vec::len).sum());
+    let mut indices = Vec::with_capacity(vecs.len() + 1);
+    indices.push(O::default());
+    for vec in vecs {
+        content.extend(vec);
+        indices.push(O::from_usize(content.len()).ok_or(FlatError::OffsetOverflow)?);
+    }
+    Ok((content, indices))
+}
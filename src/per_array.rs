@@ -0,0 +1,343 @@
+//! Operations applied to every sub-array independently. They are
+//! written once over the content and the offsets, and exposed by both
+//! `FlatVec` and `FlatArray`.
+use crate::iterator::{EnumerateIter, EnumerateIterMut};
+use crate::offset::Offset;
+use crate::{FlatArray, FlatBuilder, FlatVec};
+use std::cmp::Ordering;
+use std::slice::{Chunks, Windows};
+
+/// Returns an iterator over the sub-arrays delimited by `indices`.
+fn arrays<'a, T, O: Offset>(content: &'a [T], indices: &'a [O]) -> impl Iterator<Item = &'a [T]> {
+    indices
+        .windows(2)
+        .map(move |w| &content[w[0].to_usize()..w[1].to_usize()])
+}
+
+/// Returns an iterator over the sliding windows of `n` elements of each
+/// sub-array delimited by `indices`.
+fn iter_windows<'a, T, O: Offset>(
+    content: &'a [T],
+    indices: &'a [O],
+    n: usize,
+) -> impl Iterator<Item = Windows<'a, T>> {
+    assert!(n != 0, "window size must be non-zero");
+    arrays(content, indices).map(move |array| array.windows(n))
+}
+
+/// Returns an iterator over the chunks of `n` elements of each
+/// sub-array delimited by `indices`.
+fn iter_chunks<'a, T, O: Offset>(
+    content: &'a [T],
+    indices: &'a [O],
+    n: usize,
+) -> impl Iterator<Item = Chunks<'a, T>> {
+    assert!(n != 0, "chunk size must be non-zero");
+    arrays(content, indices).map(move |array| array.chunks(n))
+}
+
+/// Applies `f` to every sub-array delimited by `indices`, and collects
+/// the results in a new `FlatVec`.
+fn flat_map_arrays<'a, T, O, U, I, F>(content: &'a [T], indices: &'a [O], mut f: F) -> FlatVec<U>
+where
+    O: Offset,
+    F: FnMut(&'a [T]) -> I,
+    I: IntoIterator<Item = U>,
+{
+    let mut builder = FlatBuilder::with_capacity(0, indices.len().saturating_sub(1));
+    for array in arrays(content, indices) {
+        builder.push(f(array));
+    }
+    builder.build_flatvec()
+}
+
+/// Calls `f` on every sub-array delimited by `indices`, mutably.
+fn for_each_array_mut<T, O: Offset>(content: &mut [T], indices: &[O], mut f: impl FnMut(&mut [T])) {
+    for w in indices.windows(2) {
+        f(&mut content[w[0].to_usize()..w[1].to_usize()]);
+    }
+}
+
+/// Borrows the sub-array at `array_idx` among the ones delimited by
+/// `indices`.
+///
+/// # Panics
+/// Panics if `array_idx` is out of bounds.
+fn array_at<'a, T, O: Offset>(content: &'a [T], indices: &[O], array_idx: usize) -> &'a [T] {
+    let num_arrays = indices.len().saturating_sub(1);
+    assert!(
+        array_idx < num_arrays,
+        "array index {array_idx} out of bounds for {num_arrays} arrays"
+    );
+    &content[indices[array_idx].to_usize()..indices[array_idx + 1].to_usize()]
+}
+
+impl<T, O: Offset> FlatVec<T, O> {
+    /// Returns an iterator yielding, for each sub-array, an iterator over
+    /// its sliding windows of `n` elements. The windows never cross the
+    /// boundary between two sub-arrays, e.g. to extract the n-grams of
+    /// each sentence. A sub-array shorter than `n` yields no window.
+    ///
+    /// # Panics
+    /// Panics if `n` is 0.
+    pub fn iter_windows(&self, n: usize) -> impl Iterator<Item = Windows<'_, T>> {
+        iter_windows(&self.content, &self.indices, n)
+    }
+    /// Returns an iterator yielding, for each sub-array, an iterator over
+    /// its chunks of `n` elements, the last chunk of a sub-array being
+    /// shorter if `n` does not divide its length. The chunks never cross
+    /// the boundary between two sub-arrays.
+    ///
+    /// # Panics
+    /// Panics if `n` is 0.
+    pub fn iter_chunks(&self, n: usize) -> impl Iterator<Item = Chunks<'_, T>> {
+        iter_chunks(&self.content, &self.indices, n)
+    }
+    /// Applies `f` to every sub-array and collects the results in a new
+    /// `FlatVec`, with one sub-array per sub-array of the `FlatVec`.
+    pub fn flat_map_arrays<'a, U, I, F>(&'a self, f: F) -> FlatVec<U>
+    where
+        F: FnMut(&'a [T]) -> I,
+        I: IntoIterator<Item = U>,
+    {
+        flat_map_arrays(&self.content, &self.indices, f)
+    }
+    /// Returns an iterator over the sub-arrays of the `FlatVec`, along
+    /// with their index.
+    pub fn enumerate_arrays(&self) -> EnumerateIter<'_, Self, T> {
+        EnumerateIter(self.iter_arrays())
+    }
+    /// Returns an iterator over the mutable sub-arrays of the `FlatVec`,
+    /// along with their index.
+    pub fn enumerate_arrays_mut(&mut self) -> EnumerateIterMut<'_, Self, T, O> {
+        EnumerateIterMut(self.iter_arrays_mut())
+    }
+
+    /// Sorts the elements of each sub-array, independently of the other
+    /// sub-arrays. The sort is stable.
+    pub fn sort_each_array(&mut self)
+    where
+        T: Ord,
+    {
+        for_each_array_mut(&mut self.content, &self.indices, <[T]>::sort);
+    }
+    /// Sorts the elements of each sub-array with the comparator `compare`.
+    /// The sort is stable.
+    pub fn sort_each_array_by<F>(&mut self, mut compare: F)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        for_each_array_mut(&mut self.content, &self.indices, |array| {
+            array.sort_by(&mut compare)
+        });
+    }
+    /// Sorts the elements of each sub-array, without preserving the order
+    /// of equal elements.
+    pub fn sort_unstable_each_array(&mut self)
+    where
+        T: Ord,
+    {
+        for_each_array_mut(&mut self.content, &self.indices, <[T]>::sort_unstable);
+    }
+    /// Sorts the elements of each sub-array with the comparator `compare`,
+    /// without preserving the order of equal elements.
+    pub fn sort_unstable_each_array_by<F>(&mut self, mut compare: F)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        for_each_array_mut(&mut self.content, &self.indices, |array| {
+            array.sort_unstable_by(&mut compare)
+        });
+    }
+
+    /// Binary searches `x` in the sub-array at `array_idx`, which must be
+    /// sorted. See `slice::binary_search` for the returned value.
+    ///
+    /// # Panics
+    /// Panics if `array_idx` is out of bounds.
+    pub fn binary_search_in(&self, array_idx: usize, x: &T) -> Result<usize, usize>
+    where
+        T: Ord,
+    {
+        array_at(&self.content, &self.indices, array_idx).binary_search(x)
+    }
+    /// Returns the index of the first element of the sub-array at
+    /// `array_idx` for which `pred` is `false`, the sub-array being
+    /// partitioned according to `pred`. See `slice::partition_point`.
+    ///
+    /// # Panics
+    /// Panics if `array_idx` is out of bounds.
+    pub fn partition_point_in<P>(&self, array_idx: usize, pred: P) -> usize
+    where
+        P: FnMut(&T) -> bool,
+    {
+        array_at(&self.content, &self.indices, array_idx).partition_point(pred)
+    }
+}
+
+impl<T, O: Offset> FlatArray<T, O> {
+    /// See `FlatVec::iter_windows`.
+    pub fn iter_windows(&self, n: usize) -> impl Iterator<Item = Windows<'_, T>> {
+        iter_windows(&self.content, &self.indices, n)
+    }
+    /// See `FlatVec::iter_chunks`.
+    pub fn iter_chunks(&self, n: usize) -> impl Iterator<Item = Chunks<'_, T>> {
+        iter_chunks(&self.content, &self.indices, n)
+    }
+    /// See `FlatVec::flat_map_arrays`.
+    pub fn flat_map_arrays<'a, U, I, F>(&'a self, f: F) -> FlatVec<U>
+    where
+        F: FnMut(&'a [T]) -> I,
+        I: IntoIterator<Item = U>,
+    {
+        flat_map_arrays(&self.content, &self.indices, f)
+    }
+    /// See `FlatVec::enumerate_arrays`.
+    pub fn enumerate_arrays(&self) -> EnumerateIter<'_, Self, T> {
+        EnumerateIter(self.iter_arrays())
+    }
+    /// See `FlatVec::enumerate_arrays_mut`.
+    pub fn enumerate_arrays_mut(&mut self) -> EnumerateIterMut<'_, Self, T, O> {
+        EnumerateIterMut(self.iter_arrays_mut())
+    }
+
+    /// See `FlatVec::sort_each_array`.
+    pub fn sort_each_array(&mut self)
+    where
+        T: Ord,
+    {
+        for_each_array_mut(&mut self.content, &self.indices, <[T]>::sort);
+    }
+    /// See `FlatVec::sort_each_array_by`.
+    pub fn sort_each_array_by<F>(&mut self, mut compare: F)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        for_each_array_mut(&mut self.content, &self.indices, |array| {
+            array.sort_by(&mut compare)
+        });
+    }
+    /// See `FlatVec::sort_unstable_each_array`.
+    pub fn sort_unstable_each_array(&mut self)
+    where
+        T: Ord,
+    {
+        for_each_array_mut(&mut self.content, &self.indices, <[T]>::sort_unstable);
+    }
+    /// See `FlatVec::sort_unstable_each_array_by`.
+    pub fn sort_unstable_each_array_by<F>(&mut self, mut compare: F)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        for_each_array_mut(&mut self.content, &self.indices, |array| {
+            array.sort_unstable_by(&mut compare)
+        });
+    }
+
+    /// See `FlatVec::binary_search_in`.
+    pub fn binary_search_in(&self, array_idx: usize, x: &T) -> Result<usize, usize>
+    where
+        T: Ord,
+    {
+        array_at(&self.content, &self.indices, array_idx).binary_search(x)
+    }
+    /// See `FlatVec::partition_point_in`.
+    pub fn partition_point_in<P>(&self, array_idx: usize, pred: P) -> usize
+    where
+        P: FnMut(&T) -> bool,
+    {
+        array_at(&self.content, &self.indices, array_idx).partition_point(pred)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_iter_windows() {
+        let flat = FlatVec::new(vec![vec![1, 2, 3], vec![4], vec![5, 6]]);
+        let windows: Vec<Vec<&[i32]>> = flat.iter_windows(2).map(|w| w.collect()).collect();
+        assert_eq!(
+            vec![vec![&[1, 2][..], &[2, 3]], vec![], vec![&[5, 6]]],
+            windows
+        );
+        let flat_array = flat.clone().into_flatarray();
+        assert!(flat_array.iter_windows(3).flatten().eq([&[1, 2, 3][..]]));
+    }
+
+    #[test]
+    fn test_iter_chunks() {
+        let flat = FlatArray::new(vec![vec![1, 2, 3], vec![], vec![4, 5]]);
+        let chunks: Vec<Vec<&[i32]>> = flat.iter_chunks(2).map(|c| c.collect()).collect();
+        assert_eq!(vec![vec![&[1, 2][..], &[3]], vec![], vec![&[4, 5]]], chunks);
+        let flat_vec = flat.into_flatvec();
+        assert_eq!(5, flat_vec.iter_chunks(1).flatten().count());
+    }
+
+    #[test]
+    fn test_flat_map_arrays() {
+        let flat = FlatArray::new(vec![vec![1, 2, 3], vec![], vec![4]]);
+        let mapped =
+            flat.flat_map_arrays(|array| array.iter().filter(|&&x| x != 2).map(|x| x * 10));
+        let expected = FlatVec::new(vec![vec![10, 30], vec![], vec![40]]);
+        assert_eq!(expected, mapped);
+        let lengths = flat.into_flatvec().flat_map_arrays(|array| [array.len()]);
+        assert_eq!(FlatVec::new(vec![vec![3], vec![0], vec![1]]), lengths);
+    }
+
+    #[test]
+    fn test_enumerate_arrays() {
+        let mut flat = FlatVec::new(vec![vec![1, 2], vec![], vec![3]]);
+        for (i, array) in flat.enumerate_arrays_mut() {
+            array.iter_mut().for_each(|x| *x += i * 10);
+        }
+        let expected = vec![(0, &[1, 2][..]), (1, &[]), (2, &[23])];
+        assert_eq!(expected, flat.enumerate_arrays().collect::<Vec<_>>());
+        let mut flat_array = flat.into_flatarray();
+        flat_array
+            .enumerate_arrays_mut()
+            .for_each(|(i, a)| a.fill(i));
+        let expected = vec![(0, &[0, 0][..]), (1, &[]), (2, &[2])];
+        assert_eq!(expected, flat_array.enumerate_arrays().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_sort_each_array() {
+        let mut flat = FlatVec::new(vec![vec![3, 1, 2], vec![], vec![5, 4]]);
+        flat.sort_each_array();
+        assert_eq!(FlatVec::new(vec![vec![1, 2, 3], vec![], vec![4, 5]]), flat);
+        flat.sort_unstable_each_array_by(|a, b| b.cmp(a));
+        assert_eq!(FlatVec::new(vec![vec![3, 2, 1], vec![], vec![5, 4]]), flat);
+        let mut pairs = FlatArray::new(vec![vec![(1, 'b'), (0, 'c'), (1, 'a')]]);
+        pairs.sort_each_array_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            FlatArray::new(vec![vec![(0, 'c'), (1, 'b'), (1, 'a')]]),
+            pairs
+        );
+        pairs.sort_unstable_each_array();
+        assert_eq!(
+            FlatArray::new(vec![vec![(0, 'c'), (1, 'a'), (1, 'b')]]),
+            pairs
+        );
+    }
+
+    #[test]
+    fn test_binary_search_in() {
+        let flat = FlatArray::new(vec![vec![1, 3, 5], vec![], vec![2, 4, 4, 8]]);
+        assert_eq!(Ok(1), flat.binary_search_in(0, &3));
+        assert_eq!(Err(2), flat.binary_search_in(0, &4));
+        assert_eq!(Err(0), flat.binary_search_in(1, &4));
+        assert_eq!(3, flat.partition_point_in(2, |&x| x < 5));
+        let flat_vec = flat.into_flatvec();
+        assert_eq!(Ok(3), flat_vec.binary_search_in(2, &8));
+        assert_eq!(0, flat_vec.partition_point_in(1, |&x| x < 5));
+    }
+
+    #[test]
+    #[should_panic(expected = "array index 3 out of bounds for 3 arrays")]
+    fn test_binary_search_in_out_of_bounds() {
+        let flat = FlatVec::new(vec![vec![1], vec![], vec![2]]);
+        let _ = flat.binary_search_in(3, &1);
+    }
+}
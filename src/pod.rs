@@ -0,0 +1,102 @@
+use crate::{FlatArray, FlatVec, FlatView};
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// Primitive element type for which every bit pattern is a valid value
+/// and which has no padding, so that its buffers can be reinterpreted
+/// as bytes and back. The trait is sealed: it is only implemented for
+/// the primitive integer and floating-point types.
+pub trait Pod: Copy + sealed::Sealed + 'static {}
+
+macro_rules! impl_pod {
+    ($($t:ty),* $(,)?) => {
+        $(
+            impl sealed::Sealed for $t {}
+            impl Pod for $t {}
+        )*
+    };
+}
+
+impl_pod!(
+    u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64
+);
+
+/// Reinterprets `values` as its bytes, in native endianness.
+fn bytes_of<T: Pod>(values: &[T]) -> &[u8] {
+    // SAFETY: `T` has no padding, so every byte of `values` is
+    // initialized, and `u8` has no alignment requirement.
+    unsafe { std::slice::from_raw_parts(values.as_ptr().cast(), size_of_val(values)) }
+}
+
+/// Reinterprets `bytes` as a slice of `T`. Returns `None` if `bytes` is
+/// not aligned for `T` or if its length is not a multiple of the size
+/// of `T`.
+pub(crate) fn cast_bytes<T: Pod>(bytes: &[u8]) -> Option<&[T]> {
+    if !(bytes.as_ptr() as usize).is_multiple_of(align_of::<T>())
+        || !bytes.len().is_multiple_of(size_of::<T>())
+    {
+        return None;
+    }
+    // SAFETY: the pointer is aligned and the length is a whole number of
+    // `T`, and every bit pattern is a valid `T`.
+    Some(unsafe { std::slice::from_raw_parts(bytes.as_ptr().cast(), bytes.len() / size_of::<T>()) })
+}
+
+impl<T: Pod, O: Pod> FlatVec<T, O> {
+    /// Reinterprets the content and the offsets as bytes, without
+    /// copying. The bytes are in native endianness: use the `binary`
+    /// module for a portable encoding.
+    pub fn as_bytes(&self) -> (&[u8], &[u8]) {
+        (bytes_of(&self.content), bytes_of(&self.indices))
+    }
+}
+
+impl<T: Pod, O: Pod> FlatArray<T, O> {
+    /// Reinterprets the content and the offsets as bytes, without
+    /// copying. The bytes are in native endianness: use the `binary`
+    /// module for a portable encoding.
+    pub fn as_bytes(&self) -> (&[u8], &[u8]) {
+        (bytes_of(&self.content), bytes_of(&self.indices))
+    }
+}
+
+impl<'a, T: Pod> FlatView<'a, T> {
+    /// Builds a view over the bytes returned by `as_bytes` on a
+    /// `FlatVec<T>` or a `FlatArray<T>`, without copying. Returns `None`
+    /// if a buffer is misaligned or has a length that is not a multiple
+    /// of its element size, or if the offsets are invalid.
+    pub fn try_from_bytes(content: &'a [u8], indices: &'a [u8]) -> Option<Self> {
+        FlatView::new(cast_bytes(content)?, cast_bytes(indices)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_bytes_round_trip() {
+        let flat_vec = FlatVec::new(vec![vec![1.5f32, -2.0], vec![], vec![3.25]]);
+        let (content, indices) = flat_vec.as_bytes();
+        assert_eq!(3 * 4, content.len());
+        assert_eq!(4 * size_of::<usize>(), indices.len());
+        let view = FlatView::<f32>::try_from_bytes(content, indices).unwrap();
+        assert!(view.iter_arrays().eq(flat_vec.iter_arrays()));
+        let flat_array = FlatArray::<u8, u16>::try_from_nested(vec![vec![1, 2], vec![3]]).unwrap();
+        let (content, indices) = flat_array.as_bytes();
+        assert_eq!(&[1, 2, 3], content);
+        let expected: Vec<u8> = [0u16, 2, 3].iter().flat_map(|o| o.to_ne_bytes()).collect();
+        assert_eq!(expected, indices);
+    }
+
+    #[test]
+    fn test_invalid_bytes() {
+        let flat_vec = FlatVec::new(vec![vec![1u32, 2], vec![3]]);
+        let (content, indices) = flat_vec.as_bytes();
+        assert!(FlatView::<u32>::try_from_bytes(&content[1..5], indices).is_none());
+        assert!(FlatView::<u32>::try_from_bytes(&content[..10], indices).is_none());
+        assert!(FlatView::<u32>::try_from_bytes(&content[..8], indices).is_none());
+    }
+}
@@ -0,0 +1,126 @@
+//! Zero-copy deserialization of a `FlatArray<Cow<'de, str>>`. The
+//! strings borrow from the input whenever the format allows it, and
+//! are only copied when they cannot (e.g. escaped JSON strings). Use it
+//! on a field with `#[serde(borrow, with = "flatarray::serde_borrowed")]`.
+//!
+//! `FlatArray<&'de str>` borrows with the default implementation and
+//! does not need this module, but fails on strings that cannot be
+//! borrowed.
+use crate::FlatArray;
+use crate::de::check_indices;
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::borrow::Cow;
+use std::fmt;
+use std::marker::PhantomData;
+
+pub fn serialize<S>(flat_array: &FlatArray<Cow<'_, str>>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    flat_array.serialize(serializer)
+}
+
+pub fn deserialize<'de, D>(deserializer: D) -> Result<FlatArray<Cow<'de, str>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let raw = RawFlatArray::deserialize(deserializer)?;
+    check_indices(&raw.indices, raw.content.len())?;
+    Ok(FlatArray {
+        content: raw.content.into_iter().map(|s| s.0).collect(),
+        indices: raw.indices,
+    })
+}
+
+/// Same layout as the serialized `FlatArray`, with strings that borrow
+/// from the input.
+#[derive(Deserialize)]
+#[serde(rename = "FlatArray")]
+struct RawFlatArray<'a> {
+    #[serde(borrow)]
+    content: Vec<CowStr<'a>>,
+    indices: Box<[usize]>,
+}
+
+struct CowStr<'a>(Cow<'a, str>);
+
+impl<'de: 'a, 'a> Deserialize<'de> for CowStr<'a> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_str(CowStrVisitor(PhantomData))
+    }
+}
+
+struct CowStrVisitor<'a>(PhantomData<&'a str>);
+
+impl<'de: 'a, 'a> Visitor<'de> for CowStrVisitor<'a> {
+    type Value = CowStr<'a>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a string")
+    }
+    fn visit_borrowed_str<E: de::Error>(self, v: &'de str) -> Result<Self::Value, E> {
+        Ok(CowStr(Cow::Borrowed(v)))
+    }
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        Ok(CowStr(Cow::Owned(v.to_owned())))
+    }
+    fn visit_string<E: de::Error>(self, v: String) -> Result<Self::Value, E> {
+        Ok(CowStr(Cow::Owned(v)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde::de::IntoDeserializer;
+    use serde::de::value::{BorrowedStrDeserializer, Error, SeqDeserializer};
+
+    /// Serialized `FlatArray` field, deserialized as a sequence.
+    enum Field<'de> {
+        Content(Vec<&'de str>),
+        Indices(Vec<usize>),
+    }
+
+    impl<'de> IntoDeserializer<'de, Error> for Field<'de> {
+        type Deserializer = Self;
+        fn into_deserializer(self) -> Self {
+            self
+        }
+    }
+
+    impl<'de> Deserializer<'de> for Field<'de> {
+        type Error = Error;
+        fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            match self {
+                Field::Content(content) => {
+                    let strs = content.into_iter().map(BorrowedStrDeserializer::new);
+                    SeqDeserializer::new(strs).deserialize_any(visitor)
+                }
+                Field::Indices(indices) => indices.into_deserializer().deserialize_any(visitor),
+            }
+        }
+        serde::forward_to_deserialize_any! {
+            bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+            bytes byte_buf option unit unit_struct newtype_struct seq tuple
+            tuple_struct map struct enum identifier ignored_any
+        }
+    }
+
+    #[test]
+    fn test_borrowed_deserialization() {
+        let input = String::from("helloworld");
+        let fields = vec![
+            Field::Content(vec![&input[..5], &input[5..]]),
+            Field::Indices(vec![0, 1, 2]),
+        ];
+        let flat: FlatArray<Cow<str>> =
+            deserialize(SeqDeserializer::new(fields.into_iter())).unwrap();
+        let arrays: Vec<_> = flat.iter_arrays().collect();
+        assert_eq!(vec![&[Cow::from("hello")], &[Cow::from("world")]], arrays);
+        assert!(flat.iter().all(|s| matches!(s, Cow::Borrowed(_))));
+    }
+}
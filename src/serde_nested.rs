@@ -0,0 +1,150 @@
+//! Nested serde representation of the flat collections. With
+//! `#[serde(with = "flatarray::serde_nested")]`, a `FlatArray<T>` or a
+//! `FlatVec<T>` is serialized as a sequence of sequences, like the
+//! `Vec<Vec<T>>` it mimics, instead of exposing its content and
+//! offsets. Deserialization flattens the sub-arrays on the fly, without
+//! building a `Vec<Vec<T>>` first.
+use crate::FlatVec;
+use crate::iterator::{FlattenedCollection, Iter};
+use serde::de::{DeserializeSeed, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::marker::PhantomData;
+
+pub fn serialize<F, T, S>(flat: &F, serializer: S) -> Result<S::Ok, S::Error>
+where
+    F: FlattenedCollection<T>,
+    T: Serialize,
+    S: Serializer,
+{
+    serializer.collect_seq(Iter::new(flat))
+}
+
+pub fn deserialize<'de, F, T, D>(deserializer: D) -> Result<F, D::Error>
+where
+    F: From<FlatVec<T>>,
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    let flat_vec = deserializer.deserialize_seq(NestedVisitor(PhantomData))?;
+    Ok(F::from(flat_vec))
+}
+
+/// Maximum number of offsets preallocated from the size hint of the
+/// input, which cannot be trusted for allocating.
+const MAX_PREALLOCATED_INDICES: usize = 8192;
+
+struct NestedVisitor<T>(PhantomData<T>);
+
+impl<'de, T: Deserialize<'de>> Visitor<'de> for NestedVisitor<T> {
+    type Value = FlatVec<T>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a sequence of sequences")
+    }
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut content = Vec::new();
+        let hint = seq.size_hint().unwrap_or(0);
+        let mut indices = Vec::with_capacity(hint.min(MAX_PREALLOCATED_INDICES - 1) + 1);
+        indices.push(0);
+        while seq
+            .next_element_seed(ArraySeed {
+                content: &mut content,
+            })?
+            .is_some()
+        {
+            indices.push(content.len());
+        }
+        Ok(FlatVec { content, indices })
+    }
+}
+
+/// Deserializes a single sub-array at the end of `content`.
+struct ArraySeed<'a, T> {
+    content: &'a mut Vec<T>,
+}
+
+impl<'de, T: Deserialize<'de>> DeserializeSeed<'de> for ArraySeed<'_, T> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Visitor<'de> for ArraySeed<'_, T> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a sequence")
+    }
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        while let Some(element) = seq.next_element()? {
+            self.content.push(element);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::FlatArray;
+    use serde::de::IntoDeserializer;
+    use serde::de::value::Error;
+
+    #[test]
+    fn test_nested_deserialization() {
+        let nested = vec![vec![1u32, 2], vec![], vec![3]];
+        let deserializer: serde::de::value::SeqDeserializer<_, Error> =
+            nested.clone().into_deserializer();
+        let flat: FlatArray<u32> = deserialize(deserializer).unwrap();
+        assert_eq!(FlatArray::new(nested), flat);
+    }
+
+    #[derive(Deserialize, Serialize)]
+    struct Document {
+        #[serde(with = "crate::serde_nested")]
+        sentences: FlatVec<u32>,
+    }
+
+    #[test]
+    fn test_nested_field() {
+        let nested = vec![vec![vec![1u32], vec![2, 3]]];
+        let deserializer: serde::de::value::SeqDeserializer<_, Error> = nested.into_deserializer();
+        let document = Document::deserialize(deserializer).unwrap();
+        assert_eq!(FlatVec::new(vec![vec![1], vec![2, 3]]), document.sentences);
+    }
+
+    /// Sequence of empty sub-arrays claiming to hold `usize::MAX` of
+    /// them.
+    struct LyingSeq(usize);
+
+    impl<'de> SeqAccess<'de> for LyingSeq {
+        type Error = Error;
+
+        fn next_element_seed<S: DeserializeSeed<'de>>(
+            &mut self,
+            seed: S,
+        ) -> Result<Option<S::Value>, Error> {
+            if self.0 == 0 {
+                return Ok(None);
+            }
+            self.0 -= 1;
+            let empty: Vec<u32> = Vec::new();
+            seed.deserialize(empty.into_deserializer()).map(Some)
+        }
+        fn size_hint(&self) -> Option<usize> {
+            Some(usize::MAX)
+        }
+    }
+
+    #[test]
+    fn test_untrusted_size_hint() {
+        let flat = NestedVisitor::<u32>(PhantomData)
+            .visit_seq(LyingSeq(2))
+            .unwrap();
+        assert_eq!(vec![0, 0, 0], flat.indices);
+        assert!(flat.indices.capacity() <= MAX_PREALLOCATED_INDICES);
+    }
+}
@@ -0,0 +1,219 @@
+use crate::iterator::{
+    FlattenedCollection, FlattenedCollectionMut, Iter, get_offset, get_range, get_range_mut,
+};
+use crate::{FlatArray, FlatVec};
+use std::ops::{Deref, Range};
+use std::sync::Arc;
+
+/// Flat collection whose content and offsets are reference counted.
+/// Cloning it is cheap, and its sub-arrays can be handed out as owned
+/// `SharedSlice` handles, e.g. to send individual sentences to other
+/// threads or async tasks.
+#[derive(Debug, Eq, PartialEq, PartialOrd, Ord, Hash)]
+pub struct SharedFlatArray<T> {
+    pub(crate) content: Arc<[T]>,
+    pub(crate) indices: Arc<[usize]>,
+}
+
+impl<T> Clone for SharedFlatArray<T> {
+    fn clone(&self) -> Self {
+        Self {
+            content: Arc::clone(&self.content),
+            indices: Arc::clone(&self.indices),
+        }
+    }
+}
+
+impl<T> FlattenedCollection<T> for SharedFlatArray<T> {
+    fn indices_len(&self) -> usize {
+        self.indices.len()
+    }
+    fn content_len(&self) -> usize {
+        self.content.len()
+    }
+    unsafe fn get_indices(&self, index: usize) -> usize {
+        unsafe { get_offset(&self.indices, index) }
+    }
+    unsafe fn get_content(&self, range: Range<usize>) -> &[T] {
+        unsafe { get_range(&self.content, range) }
+    }
+}
+
+impl<T> FlattenedCollectionMut<T> for SharedFlatArray<T> {
+    /// # Panics
+    /// Panics if the content is shared with another `SharedFlatArray`.
+    unsafe fn get_mut_content(&mut self, range: Range<usize>) -> &mut [T] {
+        let content =
+            Arc::get_mut(&mut self.content).expect("cannot mutably borrow shared content");
+        unsafe { get_range_mut(content, range) }
+    }
+}
+
+impl<'a, T> SharedFlatArray<T> {
+    /// Returns an iterator over the content. This iterator returns the
+    /// individual elements.
+    pub fn iter(&'a self) -> std::slice::Iter<'a, T> {
+        self.content.iter()
+    }
+    /// Returns an iterator over the arrays/vectors used to build the
+    /// `SharedFlatArray`. The iterator will return a slice of type `&[T]`.
+    pub fn iter_arrays(&'a self) -> Iter<'a, SharedFlatArray<T>, T> {
+        Iter::new(self)
+    }
+    /// Returns an iterator over the arrays/vectors used to build the
+    /// `SharedFlatArray`. The iterator will return owned
+    /// `SharedSlice` handles, sharing the content of the collection.
+    pub fn iter_arrays_shared(&'a self) -> SharedIter<'a, T> {
+        SharedIter {
+            content: &self.content,
+            indices: self.indices.windows(2),
+        }
+    }
+}
+
+impl<T> Deref for SharedFlatArray<T> {
+    type Target = [T];
+    fn deref(&self) -> &Self::Target {
+        self.content.as_ref()
+    }
+}
+
+impl<T> From<FlatArray<T>> for SharedFlatArray<T> {
+    fn from(value: FlatArray<T>) -> Self {
+        Self {
+            content: value.content.into(),
+            indices: value.indices.into(),
+        }
+    }
+}
+
+impl<T> From<FlatVec<T>> for SharedFlatArray<T> {
+    fn from(value: FlatVec<T>) -> Self {
+        Self {
+            content: value.content.into(),
+            indices: value.indices.into(),
+        }
+    }
+}
+
+impl<T> FlatArray<T> {
+    /// Converts the `FlatArray` into a `SharedFlatArray`, which can then
+    /// be cloned in O(1), e.g. once per worker thread. The conversion
+    /// itself is O(n): an `Arc` keeps its counters in front of the data,
+    /// so the elements and the offsets are moved (not cloned) into newly
+    /// allocated buffers, and the old ones are freed.
+    pub fn into_shared(self) -> SharedFlatArray<T> {
+        SharedFlatArray::from(self)
+    }
+}
+
+impl<T: Clone> FlatArray<T> {
+    /// Clones the `FlatArray` into a `SharedFlatArray`.
+    pub fn to_shared(&self) -> SharedFlatArray<T> {
+        SharedFlatArray {
+            content: Arc::from(&*self.content),
+            indices: Arc::from(&*self.indices),
+        }
+    }
+}
+
+impl<T> FlatVec<T> {
+    /// Converts the `FlatVec` into a `SharedFlatArray`. Like
+    /// `FlatArray::into_shared`, this moves the elements and the offsets
+    /// into new buffers in O(n), and the result is then cloned in O(1).
+    pub fn into_shared(self) -> SharedFlatArray<T> {
+        SharedFlatArray::from(self)
+    }
+}
+
+impl<T: Clone> FlatVec<T> {
+    /// Clones the `FlatVec` into a `SharedFlatArray`.
+    pub fn to_shared(&self) -> SharedFlatArray<T> {
+        SharedFlatArray {
+            content: Arc::from(self.content.as_slice()),
+            indices: Arc::from(self.indices.as_slice()),
+        }
+    }
+}
+
+/// Owned handle over a single sub-array of a `SharedFlatArray`. It
+/// keeps the whole content alive and dereferences to `&[T]`.
+#[derive(Debug)]
+pub struct SharedSlice<T> {
+    content: Arc<[T]>,
+    range: Range<usize>,
+}
+
+impl<T> Clone for SharedSlice<T> {
+    fn clone(&self) -> Self {
+        Self {
+            content: Arc::clone(&self.content),
+            range: self.range.clone(),
+        }
+    }
+}
+
+impl<T> Deref for SharedSlice<T> {
+    type Target = [T];
+    fn deref(&self) -> &Self::Target {
+        &self.content[self.range.clone()]
+    }
+}
+
+impl<T: PartialEq> PartialEq for SharedSlice<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.deref() == other.deref()
+    }
+}
+
+impl<T: Eq> Eq for SharedSlice<T> {}
+
+/// Iterator over the sub-arrays of a `SharedFlatArray`, yielding
+/// `SharedSlice` handles.
+#[derive(Debug, Clone)]
+pub struct SharedIter<'a, T> {
+    content: &'a Arc<[T]>,
+    indices: std::slice::Windows<'a, usize>,
+}
+
+impl<T> Iterator for SharedIter<'_, T> {
+    type Item = SharedSlice<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let bounds = self.indices.next()?;
+        Some(SharedSlice {
+            content: Arc::clone(self.content),
+            range: bounds[0]..bounds[1],
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_iter_arrays_shared() {
+        let shared = SharedFlatArray::from(FlatArray::new(vec![vec![1, 2], vec![3, 4, 5]]));
+        let handles: Vec<SharedSlice<i32>> = shared.iter_arrays_shared().collect();
+        drop(shared);
+        let joined =
+            std::thread::spawn(move || handles.iter().map(|h| h.to_vec()).collect::<Vec<_>>())
+                .join()
+                .unwrap();
+        assert_eq!(vec![vec![1, 2], vec![3, 4, 5]], joined);
+    }
+
+    #[test]
+    fn test_to_shared() {
+        let flat_vec = FlatVec::new(vec![vec![1, 2], vec![3]]);
+        let shared = flat_vec.to_shared();
+        let workers: Vec<_> = (0..4).map(|_| shared.clone()).collect();
+        assert_eq!(5, Arc::strong_count(&shared.content));
+        assert!(workers.iter().all(|w| *w == shared));
+        assert_eq!(shared, flat_vec.clone().into_shared());
+        let flat_array = FlatArray::from(flat_vec);
+        assert_eq!(shared, flat_array.to_shared());
+        assert_eq!(shared, flat_array.into_shared());
+    }
+}
@@ -0,0 +1,168 @@
+//! Named POSIX shared-memory segments holding a collection in the
+//! crate's binary format, enabled by the `shm` feature. A producer
+//! copies a collection into a segment once, and any number of consumer
+//! processes map it read-only and iterate over it without their own
+//! copy, e.g. data loader workers sharing a corpus.
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+compile_error!("the shm feature needs a Linux or macOS target");
+
+use crate::binary::{Element, write_collection};
+use crate::mmap::{HEADER_LEN, Mapping, MmapFlatArray};
+use crate::pod::Pod;
+use crate::{FlatArray, FlatVec};
+use std::ffi::{CString, c_char, c_int, c_uint};
+use std::fs::File;
+use std::io;
+use std::os::unix::io::FromRawFd;
+
+#[cfg(target_os = "linux")]
+const O_CREAT: c_int = 0o100;
+#[cfg(target_os = "linux")]
+const O_EXCL: c_int = 0o200;
+#[cfg(target_os = "macos")]
+const O_CREAT: c_int = 0x200;
+#[cfg(target_os = "macos")]
+const O_EXCL: c_int = 0x800;
+const O_RDONLY: c_int = 0;
+const O_RDWR: c_int = 2;
+
+unsafe extern "C" {
+    fn shm_open(name: *const c_char, oflag: c_int, ...) -> c_int;
+    fn shm_unlink(name: *const c_char) -> c_int;
+}
+
+/// Turns `name` into the name of a POSIX segment, which starts with a
+/// `/` and contains no other one.
+fn segment_name(name: &str) -> io::Result<CString> {
+    if name.is_empty() || name.contains('/') {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "a segment name must be non-empty and contain no '/'",
+        ));
+    }
+    CString::new(format!("/{name}"))
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))
+}
+
+/// Opens the segment `name` with the `open` flags `oflag`.
+fn open_segment(name: &CString, oflag: c_int) -> io::Result<File> {
+    // SAFETY: `name` is a NUL-terminated string, and the mode is only
+    // read when creating the segment.
+    let fd = unsafe { shm_open(name.as_ptr(), oflag, 0o600 as c_uint) };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: `fd` was just opened and is owned by nobody else.
+    Ok(unsafe { File::from_raw_fd(fd) })
+}
+
+/// Named shared-memory segment holding a collection, created by
+/// `FlatVec::to_shared_memory` or `FlatArray::to_shared_memory`. The
+/// name is removed when the segment is dropped: consumers that already
+/// opened it keep their mapping, but it can no longer be opened.
+#[derive(Debug)]
+pub struct ShmSegment {
+    name: CString,
+}
+
+impl ShmSegment {
+    /// Name given to the segment when creating it, to be passed to
+    /// `FlatArray::open_shared_memory` on the consumer side.
+    pub fn name(&self) -> &str {
+        // The name was built from a `&str`, after a leading `/`.
+        &self.name.to_str().unwrap()[1..]
+    }
+    /// Creates the segment `name`, which must not exist, and writes the
+    /// collection into it.
+    fn create<T: Element>(name: &str, content: &[T], indices: &[usize]) -> io::Result<Self> {
+        let name = segment_name(name)?;
+        let file = open_segment(&name, O_CREAT | O_EXCL | O_RDWR)?;
+        // The segment is ours from now on: it is removed if writing it
+        // fails.
+        let segment = Self { name };
+        let len = HEADER_LEN + size_of::<u64>() * indices.len() + T::SIZE * content.len();
+        file.set_len(len as u64)?;
+        // SAFETY: the segment was just created, and the other processes
+        // only open it once this function returns.
+        let mut map = unsafe { Mapping::of_file_mut(&file)? };
+        write_collection(&mut map.bytes_mut(), content, indices)?;
+        Ok(segment)
+    }
+}
+
+impl Drop for ShmSegment {
+    fn drop(&mut self) {
+        // SAFETY: `name` is a NUL-terminated string.
+        unsafe { shm_unlink(self.name.as_ptr()) };
+    }
+}
+
+impl<T: Element + Pod> FlatVec<T> {
+    /// Copies the `FlatVec` into a new shared-memory segment called
+    /// `name`, which other processes can map with
+    /// `FlatArray::open_shared_memory`. Fails with
+    /// `io::ErrorKind::AlreadyExists` if the segment already exists, and
+    /// with `io::ErrorKind::InvalidInput` if `name` is empty or contains
+    /// a `/`.
+    pub fn to_shared_memory(&self, name: &str) -> io::Result<ShmSegment> {
+        ShmSegment::create(name, &self.content, &self.indices)
+    }
+}
+
+impl<T: Element + Pod> FlatArray<T> {
+    /// See `FlatVec::to_shared_memory`.
+    pub fn to_shared_memory(&self, name: &str) -> io::Result<ShmSegment> {
+        ShmSegment::create(name, &self.content, &self.indices)
+    }
+    /// Maps the shared-memory segment `name`, created by
+    /// `to_shared_memory` in this process or another one, read-only.
+    /// See `FlatArray::open_mmap` for the checks done on the segment.
+    ///
+    /// # Safety
+    /// The segment must not be modified while the returned collection is
+    /// alive.
+    pub unsafe fn open_shared_memory(name: &str) -> io::Result<MmapFlatArray<T>> {
+        let file = open_segment(&segment_name(name)?, O_RDONLY)?;
+        unsafe { MmapFlatArray::from_file(&file) }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn unique_name(name: &str) -> String {
+        format!("flatarray-{}-{name}", std::process::id())
+    }
+
+    #[test]
+    fn test_shared_memory() {
+        let name = unique_name("roundtrip");
+        let flat = FlatVec::new(vec![vec![1u32, 2, 3], vec![], vec![4]]);
+        let segment = flat.to_shared_memory(&name).unwrap();
+        assert_eq!(name, segment.name());
+        let error = flat.to_shared_memory(&name).unwrap_err();
+        assert_eq!(io::ErrorKind::AlreadyExists, error.kind());
+        let mapped = unsafe { FlatArray::<u32>::open_shared_memory(&name) }.unwrap();
+        assert!(mapped.iter_arrays().eq(flat.iter_arrays()));
+        drop(segment);
+        // The mapping outlives the name of the segment.
+        assert_eq!(&[1, 2, 3, 4], mapped.get_content());
+        assert!(unsafe { FlatArray::<u32>::open_shared_memory(&name) }.is_err());
+    }
+
+    #[test]
+    fn test_shared_memory_names() {
+        let flat = FlatArray::from(vec![vec![1u8]]);
+        for name in ["", "a/b", "a\0b"] {
+            let error = flat.to_shared_memory(name).unwrap_err();
+            assert_eq!(io::ErrorKind::InvalidInput, error.kind());
+        }
+        let name = unique_name("empty");
+        let segment = FlatArray::<u8>::from(Vec::<Vec<u8>>::new())
+            .to_shared_memory(&name)
+            .unwrap();
+        let mapped = unsafe { FlatArray::<u8>::open_shared_memory(segment.name()) }.unwrap();
+        assert_eq!(0, mapped.iter_arrays().count());
+    }
+}
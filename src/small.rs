@@ -0,0 +1,331 @@
+use crate::FlatVec;
+use std::fmt;
+use std::mem::MaybeUninit;
+
+/// Flat collection storing up to `C` elements in up to `A` sub-arrays
+/// inline, without any heap allocation. Pushing past either capacity
+/// moves the sub-arrays to a heap-allocated `FlatVec`. Meant for the
+/// common case of a handful of short sub-arrays, e.g. per request.
+pub struct SmallFlatVec<T, const C: usize, const A: usize> {
+    storage: Storage<T, C, A>,
+}
+
+enum Storage<T, const C: usize, const A: usize> {
+    Inline {
+        /// Only the elements before the end of the last sub-array are
+        /// initialized.
+        content: [MaybeUninit<T>; C],
+        /// End of every sub-array in `content`. The first one starts at
+        /// 0.
+        ends: [usize; A],
+        num_arrays: usize,
+    },
+    Heap(FlatVec<T>),
+}
+
+impl<T, const C: usize, const A: usize> Storage<T, C, A> {
+    fn empty() -> Self {
+        Storage::Inline {
+            content: [const { MaybeUninit::uninit() }; C],
+            ends: [0; A],
+            num_arrays: 0,
+        }
+    }
+}
+
+/// Number of initialized elements of an inline content.
+fn inline_len(ends: &[usize], num_arrays: usize) -> usize {
+    num_arrays.checked_sub(1).map_or(0, |i| ends[i])
+}
+
+impl<T, const C: usize, const A: usize> Drop for SmallFlatVec<T, C, A> {
+    fn drop(&mut self) {
+        if let Storage::Inline {
+            content,
+            ends,
+            num_arrays,
+        } = &mut self.storage
+        {
+            let len = inline_len(ends, *num_arrays);
+            // SAFETY: the first `len` elements are initialized, and
+            // nothing reads them after this.
+            unsafe {
+                std::ptr::drop_in_place(std::ptr::slice_from_raw_parts_mut(
+                    content.as_mut_ptr().cast::<T>(),
+                    len,
+                ))
+            };
+        }
+    }
+}
+
+impl<T: Clone, const C: usize, const A: usize> Clone for SmallFlatVec<T, C, A> {
+    fn clone(&self) -> Self {
+        match &self.storage {
+            Storage::Heap(flat_vec) => Self {
+                storage: Storage::Heap(flat_vec.clone()),
+            },
+            Storage::Inline { .. } => {
+                let mut clone = Self::new();
+                for array in self.iter_arrays() {
+                    clone.push_slice(array);
+                }
+                clone
+            }
+        }
+    }
+}
+
+impl<T: fmt::Debug, const C: usize, const A: usize> fmt::Debug for SmallFlatVec<T, C, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SmallFlatVec")
+            .field("inline", &self.is_inline())
+            .field("arrays", &self.iter_arrays().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl<T: PartialEq, const C: usize, const A: usize> PartialEq for SmallFlatVec<T, C, A> {
+    /// Compares the sub-arrays, whether they are stored inline or not.
+    fn eq(&self, other: &Self) -> bool {
+        self.num_arrays() == other.num_arrays() && self.iter_arrays().eq(other.iter_arrays())
+    }
+}
+
+impl<T: Eq, const C: usize, const A: usize> Eq for SmallFlatVec<T, C, A> {}
+
+impl<T, const C: usize, const A: usize> Default for SmallFlatVec<T, C, A> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const C: usize, const A: usize> SmallFlatVec<T, C, A> {
+    /// Creates an empty collection, stored inline.
+    pub fn new() -> Self {
+        Self {
+            storage: Storage::empty(),
+        }
+    }
+    /// Returns `true` if the sub-arrays are still stored inline.
+    pub fn is_inline(&self) -> bool {
+        matches!(self.storage, Storage::Inline { .. })
+    }
+    /// Number of sub-arrays.
+    pub fn num_arrays(&self) -> usize {
+        match &self.storage {
+            Storage::Inline { num_arrays, .. } => *num_arrays,
+            Storage::Heap(flat_vec) => flat_vec.indices.len() - 1,
+        }
+    }
+    /// Returns `true` if there are no sub-arrays.
+    pub fn is_empty(&self) -> bool {
+        self.num_arrays() == 0
+    }
+    /// Borrows the content of all the sub-arrays.
+    pub fn get_content(&self) -> &[T] {
+        match &self.storage {
+            Storage::Inline {
+                content,
+                ends,
+                num_arrays,
+            } => {
+                let len = inline_len(ends, *num_arrays);
+                // SAFETY: the first `len` elements are initialized.
+                unsafe { std::slice::from_raw_parts(content.as_ptr().cast::<T>(), len) }
+            }
+            Storage::Heap(flat_vec) => &flat_vec.content,
+        }
+    }
+    /// Pushes the elements of `items` as a new sub-array, moving the
+    /// sub-arrays to the heap if they no longer fit inline.
+    pub fn push<I: IntoIterator<Item = T>>(&mut self, items: I) {
+        let mut items = items.into_iter();
+        if let Storage::Inline {
+            content,
+            ends,
+            num_arrays,
+        } = &mut self.storage
+        {
+            let start = inline_len(ends, *num_arrays);
+            let mut end = start;
+            let mut overflow = None;
+            if *num_arrays < A {
+                // The elements written past `start` are only counted
+                // once the sub-array is complete: if `items` panics,
+                // they are leaked, not dropped twice.
+                loop {
+                    let Some(item) = items.next() else {
+                        ends[*num_arrays] = end;
+                        *num_arrays += 1;
+                        return;
+                    };
+                    if end == C {
+                        overflow = Some(item);
+                        break;
+                    }
+                    content[end].write(item);
+                    end += 1;
+                }
+            }
+            // Move the inline elements, including those of the new
+            // sub-array read so far, to the heap.
+            let spilled_arrays = std::mem::take(num_arrays);
+            let mut heap = Vec::with_capacity(end + 1 + items.size_hint().0);
+            for slot in &content[..end] {
+                // SAFETY: the first `end` elements are initialized, and
+                // no longer counted by `num_arrays`.
+                heap.push(unsafe { slot.assume_init_read() });
+            }
+            heap.extend(overflow);
+            let mut indices = Vec::with_capacity(spilled_arrays + 2);
+            indices.push(0);
+            indices.extend_from_slice(&ends[..spilled_arrays]);
+            self.storage = Storage::Heap(FlatVec {
+                content: heap,
+                indices,
+            });
+        }
+        let Storage::Heap(flat_vec) = &mut self.storage else {
+            unreachable!("the sub-arrays were moved to the heap")
+        };
+        flat_vec.content.extend(items);
+        flat_vec.indices.push(flat_vec.content.len());
+    }
+    /// Returns the i-th sub-array, or `None` if `i` is out of bounds.
+    pub fn get(&self, i: usize) -> Option<&[T]> {
+        match &self.storage {
+            Storage::Inline {
+                ends, num_arrays, ..
+            } => {
+                if i >= *num_arrays {
+                    return None;
+                }
+                let start = i.checked_sub(1).map_or(0, |j| ends[j]);
+                Some(&self.get_content()[start..ends[i]])
+            }
+            Storage::Heap(flat_vec) => {
+                let start = *flat_vec.indices.get(i)?;
+                let end = *flat_vec.indices.get(i + 1)?;
+                Some(&flat_vec.content[start..end])
+            }
+        }
+    }
+    /// Returns an iterator over the sub-arrays.
+    pub fn iter_arrays(&self) -> impl Iterator<Item = &[T]> {
+        (0..self.num_arrays()).map(|i| self.get(i).unwrap())
+    }
+    /// Converts the collection into a `FlatVec`.
+    pub fn into_flatvec(mut self) -> FlatVec<T> {
+        // `self` is left empty, so that dropping it drops nothing.
+        let storage = std::mem::replace(&mut self.storage, Storage::empty());
+        match storage {
+            Storage::Heap(flat_vec) => flat_vec,
+            Storage::Inline {
+                content,
+                ends,
+                num_arrays,
+            } => {
+                let mut flat_vec = FlatVec::with_capacity(inline_len(&ends, num_arrays), A);
+                for slot in &content[..inline_len(&ends, num_arrays)] {
+                    // SAFETY: the element is initialized, and `content`
+                    // is dropped without dropping its elements.
+                    flat_vec.content.push(unsafe { slot.assume_init_read() });
+                }
+                flat_vec.indices.extend_from_slice(&ends[..num_arrays]);
+                flat_vec
+            }
+        }
+    }
+}
+
+impl<T: Clone, const C: usize, const A: usize> SmallFlatVec<T, C, A> {
+    /// Pushes a copy of `slice` as a new sub-array, moving the sub-arrays
+    /// to the heap if they no longer fit inline.
+    pub fn push_slice(&mut self, slice: &[T]) {
+        self.push(slice.iter().cloned());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_small_flat_vec() {
+        let mut small: SmallFlatVec<u32, 4, 3> = SmallFlatVec::new();
+        assert!(small.is_empty());
+        small.push_slice(&[1, 2]);
+        small.push_slice(&[]);
+        small.push_slice(&[3]);
+        assert!(small.is_inline());
+        assert_eq!(&[1, 2, 3], small.get_content());
+        assert_eq!(Some(&[3][..]), small.get(2));
+        assert_eq!(None, small.get(3));
+        // A fourth sub-array does not fit inline.
+        small.push_slice(&[4]);
+        assert!(!small.is_inline());
+        assert_eq!(
+            vec![&[1, 2][..], &[], &[3], &[4]],
+            small.iter_arrays().collect::<Vec<_>>()
+        );
+        let flat_vec = FlatVec::new(vec![vec![1, 2], vec![], vec![3], vec![4]]);
+        assert_eq!(flat_vec, small.into_flatvec());
+    }
+
+    #[test]
+    fn test_small_flat_vec_content_spill() {
+        let mut small: SmallFlatVec<u8, 4, 8> = SmallFlatVec::default();
+        small.push_slice(&[1, 2, 3]);
+        assert_eq!(
+            FlatVec::new(vec![vec![1, 2, 3]]),
+            small.clone().into_flatvec()
+        );
+        small.push_slice(&[4, 5]);
+        assert!(!small.is_inline());
+        assert_eq!(2, small.num_arrays());
+        assert_eq!(&[1, 2, 3, 4, 5], small.get_content());
+        let mut inline: SmallFlatVec<u8, 8, 8> = SmallFlatVec::new();
+        inline.push_slice(&[1, 2, 3]);
+        inline.push_slice(&[4, 5]);
+        let mut spilled: SmallFlatVec<u8, 8, 1> = SmallFlatVec::new();
+        spilled.push_slice(&[1, 2, 3]);
+        spilled.push_slice(&[4, 5]);
+        assert_eq!(inline.into_flatvec(), spilled.clone().into_flatvec());
+        let mut other: SmallFlatVec<u8, 8, 1> = SmallFlatVec::new();
+        other.push_slice(&[1, 2, 3]);
+        assert_ne!(spilled, other);
+        other.push_slice(&[4, 5]);
+        assert_eq!(spilled, other);
+    }
+
+    #[test]
+    fn test_small_flat_vec_owned_elements() {
+        let counter = std::rc::Rc::new(());
+        let mut small: SmallFlatVec<_, 4, 3> = SmallFlatVec::new();
+        small.push(vec![counter.clone(), counter.clone()]);
+        small.push_slice(std::slice::from_ref(&counter));
+        assert_eq!(4, std::rc::Rc::strong_count(&counter));
+        let clone = small.clone();
+        assert_eq!(7, std::rc::Rc::strong_count(&counter));
+        drop(clone);
+        // Overflowing the content in the middle of a sub-array.
+        small.push(vec![counter.clone(), counter.clone()]);
+        assert!(!small.is_inline());
+        assert_eq!(
+            vec![2, 1, 2],
+            small.iter_arrays().map(<[_]>::len).collect::<Vec<_>>()
+        );
+        assert_eq!(6, std::rc::Rc::strong_count(&counter));
+        drop(small);
+        assert_eq!(1, std::rc::Rc::strong_count(&counter));
+
+        let mut strings: SmallFlatVec<String, 4, 2> = SmallFlatVec::new();
+        strings.push(["a", "b"].map(String::from));
+        let flat_vec = strings.into_flatvec();
+        assert_eq!(
+            FlatVec::new(vec![vec!["a".to_string(), "b".to_string()]]),
+            flat_vec
+        );
+    }
+}
@@ -0,0 +1,271 @@
+use crate::binary::{
+    Element, read_collection, to_u64, write_collection, write_elements, write_header,
+};
+use crate::{FlatArray, FlatBuilder, FlatVec};
+#[cfg(feature = "mmap")]
+use crate::{MmapFlatArray, Pod};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Distinguishes the spill files of a process.
+static SPILL_FILES: AtomicUsize = AtomicUsize::new(0);
+
+/// Builder for collections larger than the memory. Once the pushed
+/// sub-arrays take more than a threshold of memory, they are written to
+/// a temporary file in the crate's binary format (see the `binary`
+/// module) and the in-memory buffers are reused. The temporary file is
+/// removed when the builder is finished or dropped.
+///
+/// The builder finishes into an in-memory `FlatVec` or `FlatArray`, into
+/// a writer with `finish_to_writer`, or, with the `mmap` feature, into a
+/// memory-mapped `MmapFlatArray` with `finish_mmap`.
+#[derive(Debug)]
+pub struct SpillingFlatBuilder<T: Element> {
+    builder: FlatBuilder<T>,
+    /// Memory, in bytes, above which the sub-arrays are spilled.
+    threshold: usize,
+    dir: PathBuf,
+    /// Spill file and its path, created on the first spill.
+    file: Option<(BufWriter<File>, PathBuf)>,
+    /// Number of collections written to the spill file.
+    spilled_chunks: usize,
+}
+
+impl<T: Element> SpillingFlatBuilder<T> {
+    /// Creates a builder spilling to the temporary directory once the
+    /// in-memory sub-arrays take more than `threshold` bytes.
+    pub fn new(threshold: usize) -> Self {
+        Self::with_dir(threshold, std::env::temp_dir())
+    }
+    /// Creates a builder spilling to a file in `dir` once the in-memory
+    /// sub-arrays take more than `threshold` bytes.
+    pub fn with_dir(threshold: usize, dir: impl Into<PathBuf>) -> Self {
+        Self {
+            builder: FlatBuilder::default(),
+            threshold,
+            dir: dir.into(),
+            file: None,
+            spilled_chunks: 0,
+        }
+    }
+    /// Pushes a new sub-array, spilling to disk if the memory threshold
+    /// is passed.
+    pub fn push<I: IntoIterator<Item = T>>(&mut self, item: I) -> io::Result<()> {
+        self.builder.push(item);
+        self.spill_if_needed()
+    }
+    /// Pushes a copy of `slice` as a new sub-array, spilling to disk if
+    /// the memory threshold is passed.
+    pub fn push_slice(&mut self, slice: &[T]) -> io::Result<()>
+    where
+        T: Clone,
+    {
+        self.builder.push_slice(slice);
+        self.spill_if_needed()
+    }
+    /// Number of spills so far.
+    pub fn num_spills(&self) -> usize {
+        self.spilled_chunks
+    }
+    /// Path of the spill file, if the builder spilled.
+    pub fn spill_path(&self) -> Option<&Path> {
+        self.file.as_ref().map(|(_, path)| path.as_path())
+    }
+    /// Memory, in bytes, taken by the in-memory sub-arrays.
+    fn memory_usage(&self) -> usize {
+        self.builder.content.len() * size_of::<T>()
+            + self.builder.indices.len() * size_of::<usize>()
+    }
+    /// Creates a new file in the spill directory, named after `kind`.
+    fn create_file(&self, kind: &str) -> io::Result<(File, PathBuf)> {
+        let id = SPILL_FILES.fetch_add(1, Ordering::Relaxed);
+        let path = self
+            .dir
+            .join(format!("flatarray-{kind}-{}-{id}", std::process::id()));
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+        Ok((file, path))
+    }
+    fn spill_if_needed(&mut self) -> io::Result<()> {
+        if self.memory_usage() <= self.threshold {
+            return Ok(());
+        }
+        let writer = match &mut self.file {
+            Some((writer, _)) => writer,
+            None => {
+                let (file, path) = self.create_file("spill")?;
+                &mut self.file.insert((BufWriter::new(file), path)).0
+            }
+        };
+        write_collection(writer, &self.builder.content, &self.builder.indices)?;
+        self.builder.clear();
+        self.spilled_chunks += 1;
+        Ok(())
+    }
+    /// Calls `f` on every spilled collection, in order.
+    fn for_each_spilled(
+        &mut self,
+        mut f: impl FnMut(Vec<T>, Vec<usize>) -> io::Result<()>,
+    ) -> io::Result<()> {
+        if let Some((writer, path)) = &mut self.file {
+            writer.flush()?;
+            let mut reader = BufReader::new(File::open(path)?);
+            for _ in 0..self.spilled_chunks {
+                let (content, indices) = read_collection(&mut reader)?;
+                f(content, indices)?;
+            }
+        }
+        Ok(())
+    }
+    /// Writes every pushed sub-array to `writer` as a single collection in
+    /// the crate's binary format, without loading more than one spilled
+    /// collection in memory at a time. The result can be read back with
+    /// `FlatVec::from_reader` or `FlatArray::from_reader`.
+    pub fn finish_to_writer(mut self, mut writer: impl Write) -> io::Result<()> {
+        let (mut num_arrays, mut num_elements) = (0, 0);
+        self.for_each_spilled(|content, indices| {
+            num_arrays += indices.len() - 1;
+            num_elements += content.len();
+            Ok(())
+        })?;
+        num_arrays += self.builder.num_arrays();
+        num_elements += self.builder.len();
+        write_header::<T>(&mut writer, num_arrays + 1, num_elements)?;
+        // The offsets come first, then the elements: read the spill file
+        // once for each.
+        let mut base = 0;
+        write_elements(&mut writer, &[0u64])?;
+        let mut write_offsets = |indices: &[usize]| {
+            let offsets: Vec<usize> = indices[1..].iter().map(|o| o + base).collect();
+            base += indices.last().unwrap();
+            write_elements(&mut writer, &to_u64(&offsets))
+        };
+        self.for_each_spilled(|_, indices| write_offsets(&indices))?;
+        write_offsets(&self.builder.indices)?;
+        self.for_each_spilled(|content, _| write_elements(&mut writer, &content))?;
+        write_elements(&mut writer, &self.builder.content)
+    }
+    /// Reads the spilled sub-arrays back and returns every pushed
+    /// sub-array as a `FlatVec`.
+    pub fn finish(mut self) -> io::Result<FlatVec<T>> {
+        let mut result = FlatBuilder::default();
+        self.for_each_spilled(|content, indices| {
+            result.append(&mut FlatBuilder { content, indices });
+            Ok(())
+        })?;
+        result.append(&mut self.builder);
+        Ok(result.build_flatvec())
+    }
+    /// Same as `finish`, returning a `FlatArray`.
+    pub fn finish_flatarray(self) -> io::Result<FlatArray<T>> {
+        self.finish().map(FlatArray::from)
+    }
+    /// Writes every pushed sub-array to a new file in the spill
+    /// directory, like `finish_to_writer`, and maps it: the collection
+    /// is then paged in by the OS instead of being loaded in memory. The
+    /// file is removed as soon as it is mapped, and its space is freed
+    /// when the returned collection is dropped.
+    #[cfg(feature = "mmap")]
+    pub fn finish_mmap(self) -> io::Result<MmapFlatArray<T>>
+    where
+        T: Pod,
+    {
+        let (file, path) = self.create_file("mapped")?;
+        let result = self
+            .finish_to_writer(BufWriter::new(&file))
+            // SAFETY: the file was just created by this builder and is
+            // removed right after being mapped, so no one else writes to
+            // it.
+            .and_then(|()| unsafe { MmapFlatArray::from_file(&file) });
+        fs::remove_file(path)?;
+        result
+    }
+}
+
+impl<T: Element> Drop for SpillingFlatBuilder<T> {
+    fn drop(&mut self) {
+        if let Some((_, path)) = self.file.take() {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_spilling_builder() {
+        let mut builder = SpillingFlatBuilder::new(256);
+        let mut expected = FlatBuilder::default();
+        for i in 0..100u32 {
+            let array: Vec<u32> = (0..i % 7).map(|j| i * j).collect();
+            builder.push_slice(&array).unwrap();
+            expected.push(array);
+        }
+        assert!(builder.num_spills() > 1);
+        let path = builder.spill_path().unwrap().to_path_buf();
+        assert!(path.exists());
+        assert_eq!(expected.build_flatvec(), builder.finish().unwrap());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_spilling_builder_to_writer() {
+        let mut builder = SpillingFlatBuilder::new(64);
+        let mut expected = FlatBuilder::default();
+        for i in 0..50u16 {
+            builder.push(0..i % 5).unwrap();
+            expected.push(0..i % 5);
+        }
+        assert!(builder.num_spills() > 1);
+        let mut bytes = Vec::new();
+        builder.finish_to_writer(&mut bytes).unwrap();
+        let flat_vec = FlatVec::<u16>::from_reader(bytes.as_slice()).unwrap();
+        assert_eq!(expected.build_flatvec(), flat_vec);
+    }
+
+    #[test]
+    fn test_spilling_builder_in_memory() {
+        let mut builder = SpillingFlatBuilder::new(1 << 20);
+        builder.push([1u8, 2]).unwrap();
+        assert_eq!(0, builder.num_spills());
+        assert_eq!(None, builder.spill_path());
+        let flat_array = builder.finish_flatarray().unwrap();
+        assert_eq!(
+            vec![vec![1, 2]],
+            flat_array
+                .iter_arrays()
+                .map(|a| a.to_vec())
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn test_spilling_builder_mmap() {
+        let dir = std::env::temp_dir().join(format!("flatarray-mmap-spill-{}", std::process::id()));
+        fs::create_dir(&dir).unwrap();
+        let mut builder = SpillingFlatBuilder::with_dir(64, &dir);
+        let mut expected = FlatBuilder::default();
+        for i in 0..50u32 {
+            builder.push(0..i % 5).unwrap();
+            expected.push(0..i % 5);
+        }
+        assert!(builder.num_spills() > 1);
+        let mapped = builder.finish_mmap().unwrap();
+        assert!(
+            mapped
+                .iter_arrays()
+                .eq(expected.build_flatvec().iter_arrays())
+        );
+        // Both the spill file and the mapped file are already removed.
+        assert_eq!(0, fs::read_dir(&dir).unwrap().count());
+        fs::remove_dir(&dir).unwrap();
+    }
+}
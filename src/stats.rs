@@ -0,0 +1,109 @@
+use crate::{FlatArray, FlatVec};
+use std::ops::Range;
+
+/// Statistics over the lengths of the sub-arrays of a collection, e.g.
+/// to estimate the padding wasted by batching ragged data.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LengthStats {
+    /// Number of sub-arrays.
+    pub count: usize,
+    pub min: usize,
+    pub max: usize,
+    pub mean: f64,
+    /// 50th percentile.
+    pub median: usize,
+    /// 90th percentile.
+    pub p90: usize,
+    /// 99th percentile.
+    pub p99: usize,
+}
+
+impl LengthStats {
+    /// Computes the statistics of the sub-arrays delimited by `indices`.
+    /// Returns `None` if there are no sub-arrays.
+    fn from_indices(indices: &[usize]) -> Option<Self> {
+        let mut lengths: Vec<usize> = indices.windows(2).map(|w| w[1] - w[0]).collect();
+        lengths.sort_unstable();
+        let count = lengths.len();
+        // Nearest-rank percentile.
+        let percentile = |p: usize| lengths[(p * count).div_ceil(100).max(1) - 1];
+        Some(Self {
+            count,
+            min: *lengths.first()?,
+            max: *lengths.last()?,
+            mean: lengths.iter().sum::<usize>() as f64 / count as f64,
+            median: percentile(50),
+            p90: percentile(90),
+            p99: percentile(99),
+        })
+    }
+}
+
+/// Counts the sub-arrays delimited by `indices` in `buckets` buckets of
+/// equal width, covering the lengths from the shortest to the longest.
+fn length_histogram(indices: &[usize], buckets: usize) -> Vec<(Range<usize>, usize)> {
+    let lengths = || indices.windows(2).map(|w| w[1] - w[0]);
+    let (Some(min), Some(max)) = (lengths().min(), lengths().max()) else {
+        return Vec::new();
+    };
+    if buckets == 0 {
+        return Vec::new();
+    }
+    let width = (max - min + 1).div_ceil(buckets);
+    let mut histogram: Vec<(Range<usize>, usize)> = (0..buckets)
+        .map(|i| (min + i * width..min + (i + 1) * width, 0))
+        .collect();
+    for length in lengths() {
+        histogram[(length - min) / width].1 += 1;
+    }
+    histogram
+}
+
+impl<T> FlatVec<T> {
+    /// Statistics over the lengths of the sub-arrays, or `None` if there
+    /// are no sub-arrays.
+    pub fn length_stats(&self) -> Option<LengthStats> {
+        LengthStats::from_indices(&self.indices)
+    }
+    /// Counts the sub-arrays in `buckets` buckets of equal width, covering
+    /// the lengths from the shortest to the longest. Each bucket is
+    /// returned with its range of lengths.
+    pub fn length_histogram(&self, buckets: usize) -> Vec<(Range<usize>, usize)> {
+        length_histogram(&self.indices, buckets)
+    }
+}
+
+impl<T> FlatArray<T> {
+    /// See `FlatVec::length_stats`.
+    pub fn length_stats(&self) -> Option<LengthStats> {
+        LengthStats::from_indices(&self.indices)
+    }
+    /// See `FlatVec::length_histogram`.
+    pub fn length_histogram(&self, buckets: usize) -> Vec<(Range<usize>, usize)> {
+        length_histogram(&self.indices, buckets)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_length_stats() {
+        let lengths = [3, 1, 4, 1, 5, 9, 2, 6, 5, 3];
+        let flat_vec = FlatVec::new(lengths.iter().map(|&l| vec![0u8; l]).collect());
+        let stats = flat_vec.length_stats().unwrap();
+        assert_eq!(10, stats.count);
+        assert_eq!((1, 9), (stats.min, stats.max));
+        assert_eq!(3.9, stats.mean);
+        assert_eq!((3, 6, 9), (stats.median, stats.p90, stats.p99));
+        assert_eq!(
+            vec![(1..4, 5), (4..7, 4), (7..10, 1)],
+            flat_vec.length_histogram(3)
+        );
+        let flat_array = FlatArray::from(flat_vec);
+        assert_eq!(Some(stats), flat_array.length_stats());
+        assert_eq!(None, FlatVec::<u8>::new_empty().length_stats());
+        assert!(FlatVec::<u8>::new_empty().length_histogram(3).is_empty());
+    }
+}
@@ -1,7 +1,12 @@
+use crate::array2::FlatArray2;
 use crate::builder::FlatBuilder;
+use crate::error::FlatError;
+use crate::interner::FlatInterner;
+use crate::iterator::indices_are_valid;
 use crate::iterator::{Iter, StrIter};
 use crate::vector::FlatVec;
-use std::ops::Deref;
+use std::io::{self, BufRead, IoSlice, Write};
+use std::ops::{Deref, Index, Range};
 
 pub type FlatStr = FlatVec<u8>;
 
@@ -10,15 +15,575 @@ impl FlatStr {
         strings_iter: I,
     ) -> Self {
         let mut builder = FlatBuilder::default();
-        strings_iter.into_iter().for_each(|s| {
-            let bytes_slice = s.bytes();
-            builder.push(bytes_slice);
-        });
-        builder.build_flatvec()
+        strings_iter.into_iter().for_each(|s| builder.push_str(&s));
+        builder.build_flatstr()
     }
+    /// Reads `reader` line by line, each line becoming one string of the
+    /// `FlatStr`. The lines are read directly into the content buffer,
+    /// and the line terminators (`\n` or `\r\n`) are removed. Fails with
+    /// `io::ErrorKind::InvalidData` if a line is not valid UTF-8.
+    pub fn from_buf_read_lines<R: BufRead>(mut reader: R) -> io::Result<Self> {
+        let mut content = Vec::new();
+        let mut indices = vec![0];
+        loop {
+            let start = content.len();
+            if reader.read_until(b'\n', &mut content)? == 0 {
+                break;
+            }
+            if content.last() == Some(&b'\n') {
+                content.pop();
+                if content.last() == Some(&b'\r') {
+                    content.pop();
+                }
+            }
+            if let Err(e) = str::from_utf8(&content[start..]) {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, e));
+            }
+            indices.push(content.len());
+        }
+        Ok(Self { content, indices })
+    }
+}
+
+impl FlatStr {
+    /// Checks that every string of `bytes` is valid UTF-8 and returns
+    /// it as a `FlatStr`.
+    pub fn from_utf8(bytes: FlatVec<u8>) -> Result<Self, FlatError> {
+        bytes.validate_utf8()?;
+        Ok(bytes)
+    }
+    /// Builds a `FlatStr` from `bytes`, replacing the invalid UTF-8
+    /// sequences of each string with `U+FFFD REPLACEMENT CHARACTER`.
+    ///
+    /// # Panics
+    /// Panics if the offsets of `bytes` are decreasing or point past
+    /// the end of its content.
+    pub fn from_bytes_lossy(bytes: &FlatVec<u8>) -> Self {
+        let mut builder = FlatBuilder::default();
+        for w in bytes.indices.windows(2) {
+            builder.push_str(&String::from_utf8_lossy(&bytes.content[w[0]..w[1]]));
+        }
+        builder.build_flatstr()
+    }
+    /// Checks that the offsets are valid and that every string is valid
+    /// UTF-8. Strings cut in the middle of a character are invalid.
+    pub fn validate_utf8(&self) -> Result<(), FlatError> {
+        if !indices_are_valid(&self.indices, self.content.len()) {
+            return Err(FlatError::InvalidIndices);
+        }
+        for (index, w) in self.indices.windows(2).enumerate() {
+            if let Err(error) = str::from_utf8(&self.content[w[0]..w[1]]) {
+                return Err(FlatError::InvalidUtf8 { index, error });
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FlatStr {
+    /// Returns an iterator over owned copies of the strings.
+    pub fn iter_owned_strings(&self) -> impl Iterator<Item = String> + '_ {
+        self.iter_strings().map(String::from)
+    }
+}
+
+impl From<FlatStr> for Vec<String> {
+    fn from(value: FlatStr) -> Self {
+        value.iter_owned_strings().collect()
+    }
+}
+
+impl From<Vec<String>> for FlatStr {
+    /// The buffer of the first string is reused as the content buffer,
+    /// the other strings being appended to it.
+    fn from(value: Vec<String>) -> Self {
+        let length: usize = value.iter().map(|s| s.len()).sum();
+        let mut indices = Vec::with_capacity(value.len() + 1);
+        indices.push(0);
+        let mut strings = value.into_iter();
+        let mut content = match strings.next() {
+            None => {
+                return Self {
+                    content: Vec::new(),
+                    indices,
+                };
+            }
+            Some(first) => first.into_bytes(),
+        };
+        content.reserve_exact(length - content.len());
+        indices.push(content.len());
+        for s in strings {
+            content.extend_from_slice(s.as_bytes());
+            indices.push(content.len());
+        }
+        Self { content, indices }
+    }
+}
+
+/// Delimiter used to split a text into strings: a `char`, a `&str`, or
+/// a closure matching the delimiting characters.
+pub trait Delimiter {
+    /// Splits `text` at every occurrence of the delimiter.
+    fn split_text(self, text: &str) -> impl Iterator<Item = &str>;
+}
+
+impl Delimiter for char {
+    fn split_text(self, text: &str) -> impl Iterator<Item = &str> {
+        text.split(self)
+    }
+}
+
+impl Delimiter for &str {
+    fn split_text(self, text: &str) -> impl Iterator<Item = &str> {
+        text.split(self)
+    }
+}
+
+impl<F: FnMut(char) -> bool> Delimiter for F {
+    fn split_text(self, text: &str) -> impl Iterator<Item = &str> {
+        text.split(self)
+    }
+}
+
+impl FlatStr {
+    /// Splits `text` at every occurrence of `delimiter`. The text is
+    /// copied once, without the delimiters, into a buffer allocated
+    /// up-front. See `FlatStrView::split` to avoid the copy.
+    pub fn split(text: &str, delimiter: impl Delimiter) -> Self {
+        let mut content = Vec::with_capacity(text.len());
+        let mut indices = vec![0];
+        for piece in delimiter.split_text(text) {
+            content.extend_from_slice(piece.as_bytes());
+            indices.push(content.len());
+        }
+        Self { content, indices }
+    }
+}
+
+impl FlatStr {
+    /// Splits each string on whitespace, producing one outer array per
+    /// string whose inner arrays are its words (e.g. sentences made of
+    /// tokens). The words are copied into a single buffer, without any
+    /// per-word allocation.
+    pub fn split_words(&self) -> FlatArray2<u8> {
+        let mut content = Vec::with_capacity(self.content.len());
+        let mut inner_indices = vec![0];
+        let mut outer_indices = Vec::with_capacity(self.indices.len());
+        outer_indices.push(0);
+        for s in self.iter_strings() {
+            for word in s.split_whitespace() {
+                content.extend_from_slice(word.as_bytes());
+                inner_indices.push(content.len());
+            }
+            outer_indices.push(inner_indices.len() - 1);
+        }
+        FlatArray2 {
+            content: content.into_boxed_slice(),
+            inner_indices: inner_indices.into_boxed_slice(),
+            outer_indices: outer_indices.into_boxed_slice(),
+        }
+    }
+}
+
+/// Substring search with the Two-Way algorithm of Crochemore and
+/// Perrin, the one behind `str::find`: linear in the length of the
+/// haystack whatever the needle, with a constant amount of extra
+/// memory. The needle is factorized once, then searched in any number
+/// of haystacks.
+struct Finder<'a> {
+    needle: &'a [u8],
+    /// Position of the critical factorization of the needle.
+    crit_pos: usize,
+    /// Period of the needle, or a shift safe to use on a mismatch in
+    /// the left part when `long_period` is set.
+    period: usize,
+    /// The needle has no period shorter than half its length, so that
+    /// the matched prefix does not need to be remembered.
+    long_period: bool,
+}
+
+impl<'a> Finder<'a> {
+    /// Factorizes `needle`, which must not be empty.
+    fn new(needle: &'a [u8]) -> Self {
+        let (crit_pos_less, period_less) = Self::maximal_suffix(needle, false);
+        let (crit_pos_greater, period_greater) = Self::maximal_suffix(needle, true);
+        let (crit_pos, period) = if crit_pos_less > crit_pos_greater {
+            (crit_pos_less, period_less)
+        } else {
+            (crit_pos_greater, period_greater)
+        };
+        if needle[..crit_pos] == needle[period..period + crit_pos] {
+            Self {
+                needle,
+                crit_pos,
+                period,
+                long_period: false,
+            }
+        } else {
+            Self {
+                needle,
+                crit_pos,
+                period: crit_pos.max(needle.len() - crit_pos) + 1,
+                long_period: true,
+            }
+        }
+    }
+    /// Returns the start and the period of the maximal suffix of
+    /// `needle` for the byte order, or for the reversed order if
+    /// `reversed` is set.
+    fn maximal_suffix(needle: &[u8], reversed: bool) -> (usize, usize) {
+        let mut left = 0;
+        let mut right = 1;
+        let mut offset = 0;
+        let mut period = 1;
+        while let Some(&a) = needle.get(right + offset) {
+            let b = needle[left + offset];
+            if (a < b && !reversed) || (a > b && reversed) {
+                right += offset + 1;
+                offset = 0;
+                period = right - left;
+            } else if a == b {
+                if offset + 1 == period {
+                    right += offset + 1;
+                    offset = 0;
+                } else {
+                    offset += 1;
+                }
+            } else {
+                left = right;
+                right += 1;
+                offset = 0;
+                period = 1;
+            }
+        }
+        (left, period)
+    }
+    /// Returns the position of the first occurrence of the needle in
+    /// `haystack`.
+    fn find(&self, haystack: &[u8]) -> Option<usize> {
+        let needle = self.needle;
+        let mut position = 0;
+        // Length of the prefix of the needle known to match at
+        // `position`, only tracked for short periods.
+        let mut memory = 0;
+        'search: while position + needle.len() <= haystack.len() {
+            let window = &haystack[position..position + needle.len()];
+            let start = if self.long_period {
+                self.crit_pos
+            } else {
+                self.crit_pos.max(memory)
+            };
+            for i in start..needle.len() {
+                if needle[i] != window[i] {
+                    position += i - self.crit_pos + 1;
+                    memory = 0;
+                    continue 'search;
+                }
+            }
+            let start = if self.long_period { 0 } else { memory };
+            for i in (start..self.crit_pos).rev() {
+                if needle[i] != window[i] {
+                    position += self.period;
+                    if !self.long_period {
+                        memory = needle.len() - self.period;
+                    }
+                    continue 'search;
+                }
+            }
+            return Some(position);
+        }
+        None
+    }
+}
+
+impl FlatStr {
+    /// Returns the first occurrence of `needle` as a `(string_idx,
+    /// byte_offset)` pair, the offset being relative to the start of the
+    /// string. An empty needle matches at the start of the first string.
+    pub fn find(&self, needle: &str) -> Option<(usize, usize)> {
+        self.matches(needle).next()
+    }
+    /// Returns `true` if any of the strings contains `needle`.
+    pub fn contains(&self, needle: &str) -> bool {
+        self.find(needle).is_some()
+    }
+    /// Returns an iterator over the non-overlapping occurrences of
+    /// `needle`, as `(string_idx, byte_offset)` pairs. The content
+    /// between the first and the last offsets is scanned at once, the
+    /// string of each hit being found by bisecting the offsets; hits
+    /// spanning two strings are skipped. An empty needle matches once at
+    /// the start of every string.
+    pub fn matches<'a>(&'a self, needle: &'a str) -> impl Iterator<Item = (usize, usize)> + 'a {
+        let needle = needle.as_bytes();
+        let finder = (!needle.is_empty()).then(|| Finder::new(needle));
+        let mut pos = self.indices.first().copied().unwrap_or(0);
+        let end = self.indices.last().copied().unwrap_or(0);
+        let mut next_string = 0;
+        std::iter::from_fn(move || {
+            let Some(finder) = &finder else {
+                let i = next_string;
+                next_string += 1;
+                return (i + 1 < self.indices.len()).then_some((i, 0));
+            };
+            loop {
+                let hit = pos + finder.find(self.content.get(pos..end)?)?;
+                // Index of the last string starting at or before the hit.
+                let i = self.indices.partition_point(|&offset| offset <= hit) - 1;
+                if hit + needle.len() <= self.indices[i + 1] {
+                    pos = hit + needle.len();
+                    return Some((i, hit - self.indices[i]));
+                }
+                // Every later hit inside the i-th string also spans the
+                // next one.
+                pos = self.indices[i + 1];
+            }
+        })
+    }
+}
+
+impl FlatStr {
+    /// Returns an iterator over the characters of the i-th string, or
+    /// `None` if `i` is out of bounds or if the string is not valid UTF-8.
+    /// These are Unicode scalar values, not grapheme clusters: iterating
+    /// over graphemes needs the segmentation tables of the
+    /// `unicode-segmentation` crate, which the crate does not depend on.
+    pub fn chars_of(&self, i: usize) -> Option<std::str::Chars<'_>> {
+        self.get_str(i).map(str::chars)
+    }
+    /// Number of characters of the i-th string, as opposed to its number
+    /// of bytes. Returns `None` if `i` is out of bounds or if the string
+    /// is not valid UTF-8.
+    pub fn char_len(&self, i: usize) -> Option<usize> {
+        self.chars_of(i).map(Iterator::count)
+    }
+}
+
+impl FlatStr {
+    /// Maps every string to its dense integer id in `vocab`, interning
+    /// the unknown strings. Pass an empty `FlatInterner` to build the
+    /// vocabulary, or an existing one to reuse it.
+    pub fn encode_labels(&self, vocab: &mut FlatInterner) -> Vec<u32> {
+        self.iter_strings()
+            .map(|s| vocab.get_or_intern(s))
+            .collect()
+    }
+    /// Maps every string to its dense integer id in `vocab`, grouping the
+    /// ids into the arrays delimited by `offsets` (e.g. the offsets of
+    /// the sentences of a corpus). Returns `None` if `offsets` are not
+    /// valid offsets into the strings.
+    pub fn encode_labels_grouped(
+        &self,
+        offsets: &[usize],
+        vocab: &mut FlatInterner,
+    ) -> Option<FlatVec<u32>> {
+        let content = self.encode_labels(vocab);
+        (offsets.first() == Some(&0) && indices_are_valid(offsets, content.len())).then(|| {
+            FlatVec {
+                content,
+                indices: offsets.to_vec(),
+            }
+        })
+    }
+}
+
+impl FlatStr {
+    /// Converts every string to ASCII lower case in place, directly on
+    /// the content buffer. Non-ASCII bytes are left unchanged.
+    pub fn make_ascii_lowercase(&mut self) {
+        self.content.make_ascii_lowercase();
+    }
+    /// Converts every string to ASCII upper case in place, directly on
+    /// the content buffer. Non-ASCII bytes are left unchanged.
+    pub fn make_ascii_uppercase(&mut self) {
+        self.content.make_ascii_uppercase();
+    }
+    /// Returns a new `FlatStr` with the lowercase equivalent of every
+    /// string. The lengths may change, hence the new collection.
+    pub fn to_lowercase(&self) -> Self {
+        Self::from_strings(self.iter_strings().map(str::to_lowercase))
+    }
+    /// Returns a new `FlatStr` with the uppercase equivalent of every
+    /// string. The lengths may change, hence the new collection.
+    pub fn to_uppercase(&self) -> Self {
+        Self::from_strings(self.iter_strings().map(str::to_uppercase))
+    }
+}
+
+impl FlatStr {
+    /// Sorts the strings lexicographically, rebuilding the content and
+    /// the offsets in one pass.
+    pub fn sort_strings(&mut self) {
+        let content = &self.content;
+        let mut ranges: Vec<Range<usize>> = self.indices.windows(2).map(|w| w[0]..w[1]).collect();
+        ranges.sort_by(|a, b| content[a.clone()].cmp(&content[b.clone()]));
+        let mut sorted = Vec::with_capacity(content.len());
+        if let Some(first) = self.indices.first_mut() {
+            *first = 0;
+        }
+        for (i, range) in ranges.into_iter().enumerate() {
+            sorted.extend_from_slice(&content[range]);
+            self.indices[i + 1] = sorted.len();
+        }
+        self.content = sorted;
+    }
+    /// Removes consecutive repeated strings, compacting the content in
+    /// place. Call `sort_strings` first to remove all the duplicates, e.g.
+    /// to build a vocabulary from a token stream.
+    pub fn dedup_strings(&mut self) {
+        let Some(num_strings) = self.indices.len().checked_sub(1) else {
+            return;
+        };
+        let mut kept = 0;
+        let mut end = 0;
+        for i in 0..num_strings {
+            let (start, next) = (self.indices[i], self.indices[i + 1]);
+            if kept > 0 {
+                let last = self.indices[kept - 1]..end;
+                if self.content[last] == self.content[start..next] {
+                    continue;
+                }
+            }
+            self.content.copy_within(start..next, end);
+            self.indices[kept] = end;
+            end += next - start;
+            kept += 1;
+        }
+        self.indices.truncate(kept + 1);
+        self.indices[kept] = end;
+        self.content.truncate(end);
+    }
+}
+
+/// Strings borrowed from a single text, e.g. the pieces of a text split
+/// at a delimiter. Unlike a `FlatStr`, the strings do not need to be
+/// contiguous, so nothing is copied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlatStrView<'a> {
+    text: &'a str,
+    ranges: Vec<Range<usize>>,
+}
+
+impl<'a> FlatStrView<'a> {
+    /// Splits `text` at every occurrence of `delimiter`, only storing
+    /// the byte range of each piece.
+    pub fn split(text: &'a str, delimiter: impl Delimiter) -> Self {
+        let ranges = delimiter
+            .split_text(text)
+            .map(|piece| {
+                let start = piece.as_ptr() as usize - text.as_ptr() as usize;
+                start..start + piece.len()
+            })
+            .collect();
+        Self { text, ranges }
+    }
+    /// Number of strings.
+    pub fn len(&self) -> usize {
+        self.ranges.len()
+    }
+    /// Returns `true` if there are no strings.
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+    /// Returns the i-th string, or `None` if `i` is out of bounds.
+    pub fn get_str(&self, i: usize) -> Option<&'a str> {
+        self.ranges.get(i).map(|r| &self.text[r.clone()])
+    }
+    /// Returns an iterator over the strings.
+    pub fn iter_strings(&self) -> impl Iterator<Item = &'a str> + '_ {
+        self.ranges.iter().map(|r| &self.text[r.clone()])
+    }
+    /// Copies the strings into a `FlatStr`.
+    pub fn to_flatstr(&self) -> FlatStr {
+        FlatStr::from_strings(self.iter_strings())
+    }
+}
+
+/// Number of buffers handed to each vectored write.
+const IO_SLICES: usize = 512;
+
+impl FlatStr {
+    /// Writes all the strings to `writer`, with `separator` between two
+    /// consecutive strings. The strings and separators are handed to
+    /// the writer with vectored writes, without being copied.
+    pub fn write_to<W: Write>(&self, writer: W, separator: &str) -> io::Result<()> {
+        self.write_strings(writer, separator.as_bytes(), false)
+    }
+    /// Writes all the strings to `writer`, each of them followed by a
+    /// newline. The output can be read back with
+    /// `FlatStr::from_buf_read_lines`.
+    pub fn write_lines<W: Write>(&self, writer: W) -> io::Result<()> {
+        self.write_strings(writer, b"\n", true)
+    }
+
+    fn write_strings<W: Write>(
+        &self,
+        mut writer: W,
+        separator: &[u8],
+        terminate: bool,
+    ) -> io::Result<()> {
+        let mut slices = Vec::with_capacity(IO_SLICES);
+        let count = self.indices.len().saturating_sub(1);
+        for (i, w) in self.indices.windows(2).enumerate() {
+            slices.push(IoSlice::new(&self.content[w[0]..w[1]]));
+            if terminate || i + 1 < count {
+                slices.push(IoSlice::new(separator));
+            }
+            if slices.len() >= IO_SLICES {
+                write_all_vectored(&mut writer, &mut slices)?;
+            }
+        }
+        write_all_vectored(&mut writer, &mut slices)
+    }
+}
+
+/// Writes all of `slices` and empties it.
+fn write_all_vectored<W: Write>(writer: &mut W, slices: &mut Vec<IoSlice<'_>>) -> io::Result<()> {
+    let mut remaining = &mut slices[..];
+    while !remaining.is_empty() {
+        match writer.write_vectored(remaining) {
+            Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+            Ok(n) => IoSlice::advance_slices(&mut remaining, n),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    slices.clear();
+    Ok(())
 }
 
 impl FlatStr {
+    /// Returns the i-th string, or `None` if `i` is out of bounds or if
+    /// its bytes are not valid UTF-8.
+    pub fn get_str(&self, i: usize) -> Option<&str> {
+        let start = *self.indices.get(i)?;
+        let end = *self.indices.get(i + 1)?;
+        str::from_utf8(self.content.get(start..end)?).ok()
+    }
+}
+
+impl Index<usize> for FlatStr {
+    type Output = str;
+    /// Returns the i-th string.
+    ///
+    /// # Panics
+    /// Panics if `i` is out of bounds or if the string is not valid
+    /// UTF-8.
+    fn index(&self, i: usize) -> &str {
+        match self.get_str(i) {
+            Some(s) => s,
+            None if i + 1 < self.indices.len() => panic!("string {i} is not valid UTF-8"),
+            None => panic!(
+                "index out of bounds: there are {} strings but the index is {i}",
+                self.indices.len().saturating_sub(1)
+            ),
+        }
+    }
+}
+
+impl FlatStr {
+    /// Returns an iterator over the strings. The strings are not
+    /// checked: a `FlatStr` built from raw or deserialized bytes should
+    /// be checked with `validate_utf8` first.
     pub fn iter_strings<'a>(&'a self) -> StrIter<'a> {
         StrIter(Iter::new(self))
     }
@@ -44,4 +609,253 @@ mod test {
         let vectored: Vec<_> = Iter::new(&flat_str).collect();
         assert!(expected.len() == vectored.len())
     }
+
+    #[test]
+    fn test_from_buf_read_lines() {
+        let input = "first line\r\nsecond\n\nlast";
+        let flat_str = FlatStr::from_buf_read_lines(input.as_bytes()).unwrap();
+        let lines: Vec<_> = flat_str.iter_strings().collect();
+        assert_eq!(vec!["first line", "second", "", "last"], lines);
+        let invalid: &[u8] = b"ok\n\xff\n";
+        assert!(FlatStr::from_buf_read_lines(invalid).is_err());
+    }
+
+    #[test]
+    fn test_write_to() {
+        let (flat_str, expected) = setup_flattened_str();
+        let mut output = Vec::new();
+        flat_str.write_to(&mut output, " | ").unwrap();
+        assert_eq!(expected.join(" | ").as_bytes(), &output[..]);
+        let mut lines = Vec::new();
+        flat_str.write_lines(&mut lines).unwrap();
+        assert_eq!(flat_str, FlatStr::from_buf_read_lines(&lines[..]).unwrap());
+    }
+
+    #[test]
+    fn test_get_str() {
+        let (flat_str, expected) = setup_flattened_str();
+        assert_eq!(Some(expected[1].as_str()), flat_str.get_str(1));
+        assert_eq!(expected[2], &flat_str[2]);
+        assert_eq!(None, flat_str.get_str(3));
+        let invalid = FlatStr::from_raw("é".as_bytes(), [0, 1, 2]);
+        assert_eq!(None, invalid.get_str(0));
+    }
+
+    #[test]
+    fn test_from_utf8() {
+        let (flat_str, _) = setup_flattened_str();
+        assert_eq!(Ok(flat_str.clone()), FlatStr::from_utf8(flat_str));
+        // "é" is two bytes long: splitting it in the middle is invalid.
+        let split = FlatVec::from_raw("é".as_bytes(), [0, 1, 2]);
+        let error = FlatStr::from_utf8(split.clone()).unwrap_err();
+        assert!(matches!(error, FlatError::InvalidUtf8 { index: 0, .. }));
+        let lossy = FlatStr::from_bytes_lossy(&split);
+        assert_eq!(
+            vec!["\u{FFFD}", "\u{FFFD}"],
+            lossy.iter_strings().collect::<Vec<_>>()
+        );
+        let out_of_bounds = FlatVec::from_raw("é".as_bytes(), [0, 3]);
+        assert_eq!(
+            Err(FlatError::InvalidIndices),
+            out_of_bounds.validate_utf8()
+        );
+    }
+
+    #[test]
+    fn test_owned_strings() {
+        let (flat_str, expected) = setup_flattened_str();
+        assert_eq!(expected, flat_str.iter_owned_strings().collect::<Vec<_>>());
+        assert_eq!(flat_str, FlatStr::from(expected.clone()));
+        assert_eq!(expected, Vec::<String>::from(flat_str));
+        assert_eq!(
+            0,
+            FlatStr::from(Vec::<String>::new()).iter_strings().count()
+        );
+    }
+
+    #[test]
+    fn test_split() {
+        let text = "tok1 tok2  tok3";
+        let flat_str = FlatStr::split(text, ' ');
+        let expected = vec!["tok1", "tok2", "", "tok3"];
+        assert_eq!(expected, flat_str.iter_strings().collect::<Vec<_>>());
+        assert_eq!(b"tok1tok2tok3", &flat_str.content[..]);
+        let view = FlatStrView::split(text, "  ");
+        assert_eq!(
+            vec!["tok1 tok2", "tok3"],
+            view.iter_strings().collect::<Vec<_>>()
+        );
+        let view = FlatStrView::split(text, char::is_numeric);
+        assert_eq!(Some(" tok"), view.get_str(1));
+        assert_eq!(view.to_flatstr(), FlatStr::split(text, char::is_numeric));
+    }
+
+    #[test]
+    fn test_split_words() {
+        let flat_str = FlatStr::from_strings(["The cat  sat", "", " on\tthe mat "]);
+        let words = flat_str.split_words();
+        assert_eq!(3, words.num_outer());
+        assert_eq!(6, words.num_inner());
+        let sentences: Vec<Vec<String>> = words
+            .iter_outer()
+            .map(|s| {
+                s.iter_arrays()
+                    .map(|w| String::from_utf8(w.to_vec()).unwrap())
+                    .collect()
+            })
+            .collect();
+        assert_eq!(
+            vec![vec!["The", "cat", "sat"], vec![], vec!["on", "the", "mat"]],
+            sentences
+        );
+    }
+
+    #[test]
+    fn test_find() {
+        let flat_str = FlatStr::from_strings(["abc", "ab", "", "xaba"]);
+        assert_eq!(Some((3, 2)), flat_str.find("ba"));
+        assert_eq!(Some((3, 0)), flat_str.find("xab"));
+        assert!(flat_str.contains("xa"));
+        // "c" + "ab" spans the first two strings.
+        assert!(!flat_str.contains("cab"));
+        assert_eq!(
+            vec![(0, 0), (1, 0), (3, 1)],
+            flat_str.matches("ab").collect::<Vec<_>>()
+        );
+        assert_eq!(4, flat_str.matches("").count());
+        assert_eq!(None, flat_str.find("abx"));
+        let flat_str = FlatStr::from_strings(["xa", "aaa"]);
+        assert_eq!(vec![(1, 0)], flat_str.matches("aa").collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_finder() {
+        // Every needle of up to 5 bytes over a two-letter alphabet,
+        // against every haystack of up to 10 bytes.
+        let words = |max_len: u32| {
+            (1..=max_len).flat_map(|len| {
+                (0..1u32 << len).map(move |bits| {
+                    (0..len)
+                        .map(|i| if bits >> i & 1 == 1 { b'b' } else { b'a' })
+                        .collect::<Vec<_>>()
+                })
+            })
+        };
+        for needle in words(5) {
+            let finder = Finder::new(&needle);
+            for haystack in words(10) {
+                let expected = haystack
+                    .windows(needle.len())
+                    .position(|window| window == needle);
+                assert_eq!(
+                    expected,
+                    finder.find(&haystack),
+                    "{needle:?} in {haystack:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_find_outside_offsets() {
+        let flat_str = FlatStr::from_raw(b"xyzabcxyz".to_vec(), vec![3, 6]);
+        assert_eq!(None, flat_str.find("xyz"));
+        assert_eq!(Some((0, 1)), flat_str.find("bc"));
+        assert_eq!(vec![(0, 0)], flat_str.matches("abc").collect::<Vec<_>>());
+        let empty = FlatStr::from_raw(b"abc".to_vec(), vec![]);
+        assert!(!empty.contains("a"));
+    }
+
+    #[test]
+    fn test_case_conversion() {
+        let mut flat_str = FlatStr::from_strings(["Straße", "ÉTÉ", "MiXeD"]);
+        assert_eq!(
+            vec!["STRASSE", "ÉTÉ", "MIXED"],
+            flat_str.to_uppercase().iter_strings().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec!["straße", "été", "mixed"],
+            flat_str.to_lowercase().iter_strings().collect::<Vec<_>>()
+        );
+        flat_str.make_ascii_lowercase();
+        assert_eq!(
+            vec!["straße", "ÉtÉ", "mixed"],
+            flat_str.iter_strings().collect::<Vec<_>>()
+        );
+        flat_str.make_ascii_uppercase();
+        assert_eq!(
+            vec!["STRAßE", "ÉTÉ", "MIXED"],
+            flat_str.iter_strings().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_sort_dedup_strings() {
+        let mut flat_str = FlatStr::from_strings(["the", "cat", "the", "", "a", "cat", "cat"]);
+        flat_str.dedup_strings();
+        assert_eq!(
+            vec!["the", "cat", "the", "", "a", "cat"],
+            flat_str.iter_strings().collect::<Vec<_>>()
+        );
+        flat_str.sort_strings();
+        assert_eq!(
+            vec!["", "a", "cat", "cat", "the", "the"],
+            flat_str.iter_strings().collect::<Vec<_>>()
+        );
+        flat_str.dedup_strings();
+        assert_eq!(
+            vec!["", "a", "cat", "the"],
+            flat_str.iter_strings().collect::<Vec<_>>()
+        );
+        assert_eq!(b"acatthe", &flat_str.content[..]);
+    }
+
+    #[test]
+    fn test_sort_dedup_strings_raw_offsets() {
+        let mut flat_str = FlatStr::from_raw(b"xxbaba".to_vec(), vec![2, 4, 6]);
+        flat_str.sort_strings();
+        assert_eq!(vec![0, 2, 4], flat_str.indices);
+        assert_eq!(
+            vec!["ba", "ba"],
+            flat_str.iter_strings().collect::<Vec<_>>()
+        );
+        flat_str.dedup_strings();
+        assert_eq!(vec!["ba"], flat_str.iter_strings().collect::<Vec<_>>());
+        let mut empty = FlatStr::from_raw(Vec::new(), Vec::new());
+        empty.sort_strings();
+        empty.dedup_strings();
+        assert!(empty.indices.is_empty());
+    }
+
+    #[test]
+    fn test_chars_of() {
+        let flat_str = FlatStr::from_strings(["héllo", "", "日本"]);
+        assert_eq!(
+            vec!['h', 'é', 'l', 'l', 'o'],
+            flat_str.chars_of(0).unwrap().collect::<Vec<_>>()
+        );
+        assert_eq!(Some(5), flat_str.char_len(0));
+        assert_eq!(Some(0), flat_str.char_len(1));
+        assert_eq!(Some(2), flat_str.char_len(2));
+        assert_eq!(None, flat_str.char_len(3));
+    }
+
+    #[test]
+    fn test_encode_labels() {
+        let labels = FlatStr::from_strings(["B-PER", "O", "O", "B-LOC", "O"]);
+        let mut vocab = FlatInterner::new();
+        assert_eq!(vec![0, 1, 1, 2, 1], labels.encode_labels(&mut vocab));
+        let encoded = labels
+            .encode_labels_grouped(&[0, 3, 5], &mut vocab)
+            .unwrap();
+        assert_eq!(
+            vec![vec![0, 1, 1], vec![2, 1]],
+            encoded
+                .iter_arrays()
+                .map(|a| a.to_vec())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(3, vocab.len());
+        assert_eq!(None, labels.encode_labels_grouped(&[0, 6], &mut vocab));
+    }
 }
@@ -0,0 +1,155 @@
+use crate::FlatBuilder;
+use crate::binary::{Element, VERSION, read_elements, write_elements};
+use std::io::{self, Read, Write};
+use std::marker::PhantomData;
+
+/// Magic bytes starting every stream of sub-arrays.
+pub const STREAM_MAGIC: [u8; 4] = *b"FLSM";
+
+/// Writes sub-arrays one at a time, without knowing their number in
+/// advance. The stream starts with the `STREAM_MAGIC` bytes, the format
+/// version (`u16`), the element tag and size (one byte each), and is
+/// followed by each sub-array as its length (`u64`) and its elements,
+/// all little-endian. The stream ends with the input.
+#[derive(Debug)]
+pub struct FlatStreamWriter<W: Write, T> {
+    writer: W,
+    phantom_data: PhantomData<T>,
+}
+
+impl<W: Write, T: Element> FlatStreamWriter<W, T> {
+    /// Writes the stream header and returns the writer.
+    pub fn new(mut writer: W) -> io::Result<Self> {
+        writer.write_all(&STREAM_MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        writer.write_all(&[T::TAG, T::SIZE as u8])?;
+        Ok(Self {
+            writer,
+            phantom_data: PhantomData,
+        })
+    }
+    /// Appends a sub-array to the stream.
+    pub fn write_array(&mut self, array: &[T]) -> io::Result<()> {
+        self.writer.write_all(&(array.len() as u64).to_le_bytes())?;
+        write_elements(&mut self.writer, array)
+    }
+    /// Appends every sub-array pushed into `builder` to the stream, and
+    /// empties the builder so that it can be filled again.
+    pub fn write_builder(&mut self, builder: &mut FlatBuilder<T>) -> io::Result<()> {
+        for w in builder.indices.windows(2) {
+            self.write_array(&builder.content[w[0]..w[1]])?;
+        }
+        builder.content.clear();
+        builder.indices.truncate(1);
+        Ok(())
+    }
+    /// Flushes and returns the underlying writer.
+    pub fn into_inner(mut self) -> io::Result<W> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+/// Reads back, one sub-array at a time, a stream written by a
+/// `FlatStreamWriter`.
+#[derive(Debug)]
+pub struct FlatStreamReader<R: Read, T> {
+    reader: R,
+    phantom_data: PhantomData<T>,
+}
+
+impl<R: Read, T: Element> FlatStreamReader<R, T> {
+    /// Reads and checks the stream header.
+    pub fn new(mut reader: R) -> io::Result<Self> {
+        let mut header = [0; 8];
+        reader.read_exact(&mut header)?;
+        let invalid = |message| Err(io::Error::new(io::ErrorKind::InvalidData, message));
+        if header[..4] != STREAM_MAGIC {
+            return invalid("not a stream of sub-arrays: wrong magic bytes");
+        }
+        if u16::from_le_bytes([header[4], header[5]]) != VERSION {
+            return invalid("unsupported format version");
+        }
+        if header[6] != T::TAG || usize::from(header[7]) != T::SIZE {
+            return invalid("the stream holds another element type");
+        }
+        Ok(Self {
+            reader,
+            phantom_data: PhantomData,
+        })
+    }
+    /// Reads the next sub-array, or `None` at the end of the stream.
+    pub fn read_array(&mut self) -> io::Result<Option<Vec<T>>> {
+        match self.read_len()? {
+            None => Ok(None),
+            Some(len) => read_elements(&mut self.reader, len).map(Some),
+        }
+    }
+    /// Reads the next sub-array and pushes it into `builder`. Returns
+    /// `false` at the end of the stream.
+    pub fn read_into(&mut self, builder: &mut FlatBuilder<T>) -> io::Result<bool> {
+        match self.read_array()? {
+            None => Ok(false),
+            Some(array) => {
+                builder.push(array);
+                Ok(true)
+            }
+        }
+    }
+
+    /// Reads the length of the next sub-array, or `None` if the input
+    /// ends right before it.
+    fn read_len(&mut self) -> io::Result<Option<usize>> {
+        let mut bytes = [0; 8];
+        let mut read = 0;
+        while read < bytes.len() {
+            match self.reader.read(&mut bytes[read..]) {
+                Ok(0) if read == 0 => return Ok(None),
+                Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+                Ok(n) => read += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        usize::try_from(u64::from_le_bytes(bytes))
+            .map(Some)
+            .map_err(|_| {
+                io::Error::new(io::ErrorKind::InvalidData, "length does not fit in memory")
+            })
+    }
+}
+
+impl<R: Read, T: Element> Iterator for FlatStreamReader<R, T> {
+    type Item = io::Result<Vec<T>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_array().transpose()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::FlatVec;
+
+    #[test]
+    fn test_stream_roundtrip() {
+        let mut writer = FlatStreamWriter::new(Vec::new()).unwrap();
+        let mut builder = FlatBuilder::default();
+        builder.push([1u16, 2]);
+        builder.push([]);
+        writer.write_builder(&mut builder).unwrap();
+        builder.push([3]);
+        writer.write_builder(&mut builder).unwrap();
+        let bytes = writer.into_inner().unwrap();
+
+        let mut reader = FlatStreamReader::new(&bytes[..]).unwrap();
+        let mut builder = FlatBuilder::default();
+        while reader.read_into(&mut builder).unwrap() {}
+        let expected = FlatVec::new(vec![vec![1u16, 2], vec![], vec![3]]);
+        assert_eq!(expected, builder.build_flatvec());
+
+        let truncated = FlatStreamReader::<_, u16>::new(&bytes[..bytes.len() - 1]).unwrap();
+        assert!(truncated.last().unwrap().is_err());
+    }
+}
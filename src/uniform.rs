@@ -0,0 +1,177 @@
+use crate::iterator::{
+    FlattenedCollection, FlattenedCollectionMut, Iter, get_range, get_range_mut,
+};
+use crate::{FlatArray, FlatVec};
+use std::ops::{Deref, Range};
+
+/// Flat collection whose sub-arrays all have the same length, the
+/// `stride`. The offsets are computed from the stride instead of being
+/// stored, which saves one `usize` per sub-array.
+#[derive(Debug, Eq, PartialEq, PartialOrd, Ord, Hash, Clone)]
+pub struct UniformFlatArray<T> {
+    pub(crate) content: Box<[T]>,
+    pub(crate) stride: usize,
+}
+
+impl<T> FlattenedCollection<T> for UniformFlatArray<T> {
+    fn indices_len(&self) -> usize {
+        self.num_arrays() + 1
+    }
+    fn content_len(&self) -> usize {
+        self.content.len()
+    }
+    unsafe fn get_indices(&self, index: usize) -> usize {
+        index * self.stride
+    }
+    unsafe fn get_content(&self, range: Range<usize>) -> &[T] {
+        unsafe { get_range(&self.content, range) }
+    }
+}
+
+impl<T> FlattenedCollectionMut<T> for UniformFlatArray<T> {
+    unsafe fn get_mut_content(&mut self, range: Range<usize>) -> &mut [T] {
+        unsafe { get_range_mut(&mut self.content, range) }
+    }
+}
+
+impl<T> UniformFlatArray<T> {
+    /// Splits `content` into sub-arrays of length `stride`. Returns
+    /// `None` if `stride` is zero or does not divide the length of the
+    /// content.
+    pub fn new(content: impl Into<Box<[T]>>, stride: usize) -> Option<Self> {
+        let content = content.into();
+        if stride == 0 || content.len() % stride != 0 {
+            return None;
+        }
+        Some(Self { content, stride })
+    }
+    /// Length shared by all the sub-arrays.
+    pub fn stride(&self) -> usize {
+        self.stride
+    }
+    /// Number of sub-arrays.
+    pub fn num_arrays(&self) -> usize {
+        self.content.len() / self.stride
+    }
+    /// Borrows the content of the UniformFlatArray as a slice.
+    pub fn get_content(&self) -> &[T] {
+        self.content.as_ref()
+    }
+}
+
+impl<'a, T> UniformFlatArray<T> {
+    /// Returns an iterator over the content. This iterator returns the
+    /// individual elements.
+    pub fn iter(&'a self) -> std::slice::Iter<'a, T> {
+        self.content.iter()
+    }
+    /// Returns an iterator over the sub-arrays. The iterator will
+    /// return a slice of type `&[T]`.
+    pub fn iter_arrays(&'a self) -> Iter<'a, UniformFlatArray<T>, T> {
+        Iter::new(self)
+    }
+    /// Returns an iterator over the sub-arrays. The iterator will
+    /// return a slice of type `&mut [T]`.
+    pub fn iter_arrays_mut(&'a mut self) -> std::slice::ChunksExactMut<'a, T> {
+        self.content.chunks_exact_mut(self.stride)
+    }
+}
+
+impl<T> Deref for UniformFlatArray<T> {
+    type Target = [T];
+    fn deref(&self) -> &Self::Target {
+        self.content.as_ref()
+    }
+}
+
+impl<T> From<UniformFlatArray<T>> for FlatArray<T> {
+    fn from(value: UniformFlatArray<T>) -> Self {
+        let indices: Vec<usize> = (0..=value.num_arrays()).map(|i| i * value.stride).collect();
+        Self {
+            content: value.content,
+            indices: indices.into_boxed_slice(),
+        }
+    }
+}
+
+impl<T> From<UniformFlatArray<T>> for FlatVec<T> {
+    fn from(value: UniformFlatArray<T>) -> Self {
+        let indices = (0..=value.num_arrays()).map(|i| i * value.stride).collect();
+        Self {
+            content: value.content.into_vec(),
+            indices,
+        }
+    }
+}
+
+/// Returns the common length of the sub-arrays described by `indices`
+/// if it is non zero and the offsets start at 0.
+fn uniform_stride(indices: &[usize]) -> Option<usize> {
+    let stride = indices.get(1)?.checked_sub(*indices.first()?)?;
+    if indices[0] == 0
+        && stride > 0
+        && indices
+            .windows(2)
+            .all(|w| w[1].checked_sub(w[0]) == Some(stride))
+    {
+        Some(stride)
+    } else {
+        None
+    }
+}
+
+impl<T> TryFrom<FlatArray<T>> for UniformFlatArray<T> {
+    type Error = FlatArray<T>;
+    /// Fails, giving the `FlatArray` back, if its sub-arrays do not all
+    /// have the same non-zero length or if it is empty.
+    fn try_from(value: FlatArray<T>) -> Result<Self, Self::Error> {
+        match uniform_stride(&value.indices) {
+            Some(stride) if value.content.len() == stride * (value.indices.len() - 1) => Ok(Self {
+                content: value.content,
+                stride,
+            }),
+            _ => Err(value),
+        }
+    }
+}
+
+impl<T> TryFrom<FlatVec<T>> for UniformFlatArray<T> {
+    type Error = FlatVec<T>;
+    /// Fails, giving the `FlatVec` back, if its sub-arrays do not all
+    /// have the same non-zero length or if it is empty.
+    fn try_from(value: FlatVec<T>) -> Result<Self, Self::Error> {
+        match uniform_stride(&value.indices) {
+            Some(stride) if value.content.len() == stride * (value.indices.len() - 1) => Ok(Self {
+                content: value.content.into_boxed_slice(),
+                stride,
+            }),
+            _ => Err(value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_iter_uniform() {
+        let mut uniform = UniformFlatArray::new(vec![1, 2, 3, 4, 5, 6], 2).unwrap();
+        let actual: Vec<_> = uniform.iter_arrays().collect();
+        let expected: Vec<&[i32]> = vec![&[1, 2], &[3, 4], &[5, 6]];
+        assert_eq!(expected, actual);
+        uniform.iter_arrays_mut().for_each(|a| a.reverse());
+        assert_eq!(&[2, 1, 4, 3, 6, 5], &uniform[..]);
+        assert!(UniformFlatArray::new(vec![1, 2, 3], 2).is_none());
+    }
+
+    #[test]
+    fn test_uniform_conversions() {
+        let flat_array = FlatArray::new(vec![vec![1, 2], vec![3, 4]]);
+        let uniform = UniformFlatArray::try_from(flat_array.clone()).unwrap();
+        assert_eq!(2, uniform.stride());
+        assert_eq!(flat_array, FlatArray::from(uniform));
+        let ragged = FlatVec::new(vec![vec![1, 2], vec![3]]);
+        assert_eq!(Err(ragged.clone()), UniformFlatArray::try_from(ragged));
+    }
+}
@@ -1,30 +1,43 @@
-use crate::iterator::{FlattenedCollection, Iter, IterMut};
-use serde::{Deserialize, Serialize};
+use crate::FlatArray;
+use crate::error::FlatError;
+use crate::iterator::{
+    FlattenedCollection, FlattenedCollectionMut, Iter, IterMut, get_offset, get_range,
+    get_range_mut,
+};
+use crate::offset::{Offset, convert_offsets, flatten_nested};
+#[cfg(feature = "serde")]
+use serde::Serialize;
 use std::borrow::Cow;
 
-#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 #[derive(Debug, Eq, PartialEq, PartialOrd, Ord, Hash, Clone)]
-pub struct FlatVec<T> {
+pub struct FlatVec<T, O = usize> {
     pub(crate) content: Vec<T>,
-    pub(crate) indices: Vec<usize>,
+    pub(crate) indices: Vec<O>,
 }
 
-impl<T> FlattenedCollection<T> for FlatVec<T> {
+impl<T, O: Offset> FlattenedCollection<T> for FlatVec<T, O> {
     fn indices_len(&self) -> usize {
         self.indices.len()
     }
+    fn content_len(&self) -> usize {
+        self.content.len()
+    }
     unsafe fn get_indices(&self, index: usize) -> usize {
-        unsafe { *self.indices.get_unchecked(index) }
+        unsafe { get_offset(&self.indices, index) }
     }
     unsafe fn get_content(&self, range: std::ops::Range<usize>) -> &[T] {
-        unsafe { self.content.get_unchecked(range) }
+        unsafe { get_range(&self.content, range) }
     }
+}
+
+impl<T, O: Offset> FlattenedCollectionMut<T> for FlatVec<T, O> {
     unsafe fn get_mut_content(&mut self, range: std::ops::Range<usize>) -> &mut [T] {
-        unsafe { self.content.get_unchecked_mut(range) }
+        unsafe { get_range_mut(&mut self.content, range) }
     }
 }
 
-impl<'a, T> FlatVec<T> {
+impl<'a, T, O: Offset> FlatVec<T, O> {
     /// Returns an iterator over the content. This iterator returns the
     /// individual elements.
     pub fn iter(&'a self) -> std::slice::Iter<'a, T> {
@@ -32,13 +45,47 @@ impl<'a, T> FlatVec<T> {
     }
     /// Returns an iterator over the arrays/vectors used to build the
     /// `FlatVec`. The iterator will return a slice of type `&[T]`.
-    pub fn iter_arrays(&'a self) -> Iter<'a, FlatVec<T>, T> {
+    pub fn iter_arrays(&'a self) -> Iter<'a, FlatVec<T, O>, T> {
         Iter::new(self)
     }
     /// Returns an iterator over the arrays/vectors used to build the
     /// `FlatVec`. The iterator will return a slice of type `&mut [T]`.
-    pub fn iter_arrays_mut(&'a mut self) -> IterMut<'a, FlatVec<T>, T> {
-        IterMut::new(self)
+    pub fn iter_arrays_mut(&'a mut self) -> IterMut<'a, FlatVec<T, O>, T, O> {
+        IterMut::new(&self.indices, &mut self.content)
+    }
+}
+
+impl<T, O: Offset> FlatVec<T, O> {
+    /// Builds the `FlatVec` from nested vectors, storing the offsets as
+    /// `O`. Fails with `FlatError::OffsetOverflow` if an offset does not
+    /// fit in `O`.
+    pub fn try_from_nested(vecs: Vec<Vec<T>>) -> Result<Self, FlatError> {
+        let (content, indices) = flatten_nested(vecs)?;
+        Ok(Self { content, indices })
+    }
+    /// Converts the offsets back to `usize`.
+    pub fn into_usize_offsets(self) -> FlatVec<T> {
+        FlatVec {
+            content: self.content,
+            indices: self.indices.into_iter().map(O::to_usize).collect(),
+        }
+    }
+}
+
+impl<T> FlatVec<T> {
+    /// Converts the offsets to a smaller type `O`, e.g. `u32`. Fails with
+    /// `FlatError::OffsetOverflow` if an offset does not fit in `O`.
+    pub fn try_into_offsets<O: Offset>(self) -> Result<FlatVec<T, O>, FlatError> {
+        Ok(FlatVec {
+            indices: convert_offsets(&self.indices)?,
+            content: self.content,
+        })
+    }
+}
+
+impl<T> Default for FlatVec<T> {
+    fn default() -> Self {
+        Self::new_empty()
     }
 }
 
@@ -46,12 +93,271 @@ impl<T> FlatVec<T> {
     pub fn new(vecs: Vec<Vec<T>>) -> Self {
         Self::from(vecs)
     }
+    /// Creates a `FlatVec` without any sub-array.
+    pub fn new_empty() -> Self {
+        Self {
+            content: Vec::new(),
+            indices: vec![0],
+        }
+    }
+    /// Creates a `FlatVec` with a single sub-array made of `items`.
+    pub fn from_single(items: impl Into<Vec<T>>) -> Self {
+        let content = items.into();
+        let indices = vec![0, content.len()];
+        Self { content, indices }
+    }
     pub fn from_raw(content: impl Into<Vec<T>>, indices: impl Into<Vec<usize>>) -> Self {
         Self {
             content: content.into(),
             indices: indices.into(),
         }
     }
+    /// Creates an empty `FlatVec` with room for `content_capacity`
+    /// elements spread over `arrays_capacity` sub-arrays.
+    pub fn with_capacity(content_capacity: usize, arrays_capacity: usize) -> Self {
+        let mut indices = Vec::with_capacity(arrays_capacity + 1);
+        indices.push(0);
+        Self {
+            content: Vec::with_capacity(content_capacity),
+            indices,
+        }
+    }
+    /// Number of elements the content buffer can hold without
+    /// reallocating.
+    pub fn capacity(&self) -> usize {
+        self.content.capacity()
+    }
+    /// Number of sub-arrays the offsets buffer can hold without
+    /// reallocating.
+    pub fn arrays_capacity(&self) -> usize {
+        self.indices.capacity().saturating_sub(1)
+    }
+    /// Reserves room for at least `additional` more elements, with the
+    /// amortized growth of `Vec::reserve`.
+    pub fn reserve(&mut self, additional: usize) {
+        self.content.reserve(additional);
+    }
+    /// Reserves room for at least `additional` more sub-arrays, with the
+    /// amortized growth of `Vec::reserve`.
+    pub fn reserve_arrays(&mut self, additional: usize) {
+        self.indices.reserve(additional);
+    }
+    /// Consumes the `FlatVec`, returning its content without copying.
+    pub fn into_content(self) -> Vec<T> {
+        self.content
+    }
+    /// Consumes the `FlatVec`, returning its offsets without copying.
+    pub fn into_indices(self) -> Vec<usize> {
+        self.indices
+    }
+    /// Consumes the `FlatVec`, returning its content and its offsets.
+    pub fn into_parts(self) -> (Vec<T>, Vec<usize>) {
+        (self.content, self.indices)
+    }
+    /// Converts the `FlatVec` into a `FlatArray`. The buffers are boxed,
+    /// which only reallocates them if they have spare capacity.
+    pub fn into_flatarray(self) -> FlatArray<T> {
+        FlatArray {
+            content: self.content.into_boxed_slice(),
+            indices: self.indices.into_boxed_slice(),
+        }
+    }
+    /// Number of heap bytes allocated for the content and the offsets,
+    /// spare capacity included. Heap memory owned by the elements
+    /// themselves (e.g. the buffers of `String`s) is not counted.
+    pub fn mem_usage(&self) -> usize {
+        self.content.capacity() * size_of::<T>() + self.indices.capacity() * size_of::<usize>()
+    }
+    /// Shrinks the capacity of both the content and the offsets as much
+    /// as possible.
+    pub fn shrink_to_fit(&mut self) {
+        self.content.shrink_to_fit();
+        self.indices.shrink_to_fit();
+    }
+    /// Removes the consecutive repeated elements inside each sub-array,
+    /// like `Vec::dedup`, then compacts the content and shifts the
+    /// offsets in a single pass. Equal elements at the end of a sub-array
+    /// and at the start of the next one are both kept.
+    pub fn dedup_within_arrays(&mut self)
+    where
+        T: PartialEq,
+    {
+        self.compact_arrays(|_, last, element| last != Some(element));
+    }
+    /// Keeps only the elements for which `keep(array_index, element)`
+    /// returns `true`, then compacts the content and shrinks the
+    /// sub-arrays in a single pass. Sub-arrays whose elements are all
+    /// removed are kept, empty.
+    pub fn retain_elements<F>(&mut self, mut keep: F)
+    where
+        F: FnMut(usize, &T) -> bool,
+    {
+        self.compact_arrays(|i, _, element| keep(i, element));
+    }
+    /// Splits the i-th sub-array in two at element position `pos`: the
+    /// first `pos` elements stay in the i-th sub-array and the other ones
+    /// form the (i + 1)-th sub-array. Only an offset is inserted, the
+    /// content is not moved.
+    ///
+    /// # Panics
+    /// Panics if `i` is out of bounds or `pos` is larger than the length
+    /// of the i-th sub-array.
+    pub fn split_array_at(&mut self, i: usize, pos: usize) {
+        let num_arrays = self.indices.len().saturating_sub(1);
+        assert!(
+            i < num_arrays,
+            "array index {i} out of bounds for {num_arrays} arrays"
+        );
+        let (start, end) = (self.indices[i], self.indices[i + 1]);
+        assert!(
+            pos <= end - start,
+            "split position {pos} out of bounds for array of length {}",
+            end - start
+        );
+        self.indices.insert(i + 1, start + pos);
+    }
+    /// Replaces the content of the i-th sub-array by `items`, which can
+    /// be of a different length. The tail of the content is shifted and
+    /// the following offsets are fixed in a single pass.
+    ///
+    /// # Panics
+    /// Panics if `i` is out of bounds.
+    pub fn replace_array<I: IntoIterator<Item = T>>(&mut self, i: usize, items: I) {
+        let num_arrays = self.indices.len().saturating_sub(1);
+        assert!(
+            i < num_arrays,
+            "array index {i} out of bounds for {num_arrays} arrays"
+        );
+        let (start, end) = (self.indices[i], self.indices[i + 1]);
+        let len = self.content.len();
+        self.content.splice(start..end, items);
+        let new_end = end + self.content.len() - len;
+        for offset in &mut self.indices[i + 1..] {
+            *offset = *offset - end + new_end;
+        }
+    }
+    /// Keeps the elements for which `keep(array_index, last, element)`
+    /// returns `true`, `last` being the last element kept in the same
+    /// sub-array, then drops the other ones and shifts the offsets.
+    fn compact_arrays<F>(&mut self, mut keep: F)
+    where
+        F: FnMut(usize, Option<&T>, &T) -> bool,
+    {
+        let Some(&first) = self.indices.first() else {
+            return;
+        };
+        let mut read = first;
+        let mut write = first;
+        for i in 1..self.indices.len() {
+            let array_start = write;
+            while read < self.indices[i] {
+                let last = (write > array_start).then(|| &self.content[write - 1]);
+                if keep(i - 1, last, &self.content[read]) {
+                    self.content.swap(read, write);
+                    write += 1;
+                }
+                read += 1;
+            }
+            self.indices[i] = write;
+        }
+        self.content.drain(write..read);
+    }
+    /// Segments a flat `Vec<T>` into sub-arrays, starting a new
+    /// sub-array between two consecutive elements `prev` and `next`
+    /// whenever `is_boundary(prev, next)` returns `true`. The content
+    /// is moved as is: only the offsets are written.
+    pub fn partition_by<F>(content: Vec<T>, mut is_boundary: F) -> Self
+    where
+        F: FnMut(&T, &T) -> bool,
+    {
+        let mut indices = vec![0];
+        for (i, pair) in content.windows(2).enumerate() {
+            if is_boundary(&pair[0], &pair[1]) {
+                indices.push(i + 1);
+            }
+        }
+        if !content.is_empty() {
+            indices.push(content.len());
+        }
+        Self { content, indices }
+    }
+}
+
+impl<T> FlatVec<T> {
+    /// Groups the values of an iterator of `(key, value)` pairs sorted
+    /// by key: each run of equal keys becomes one sub-array. Returns
+    /// the keys of the runs, in order, along with the grouped values.
+    pub fn group_by_key<K, I>(iter: I) -> (Vec<K>, Self)
+    where
+        K: PartialEq,
+        I: IntoIterator<Item = (K, T)>,
+    {
+        let mut keys: Vec<K> = Vec::new();
+        let mut content = Vec::new();
+        let mut indices = vec![0];
+        for (key, value) in iter {
+            if keys.last() != Some(&key) {
+                if !keys.is_empty() {
+                    indices.push(content.len());
+                }
+                keys.push(key);
+            }
+            content.push(value);
+        }
+        if !keys.is_empty() {
+            indices.push(content.len());
+        }
+        (keys, Self { content, indices })
+    }
+}
+
+impl<T: Send> FlatVec<T> {
+    /// Build the `FlatVec` from a `Vec<Vec<T>>`, copying the
+    /// sub-arrays on several threads when the `parallel` feature is
+    /// enabled. The offsets are computed first, then each thread moves
+    /// a batch of sub-arrays into its own disjoint region of a
+    /// preallocated buffer. Only worth it on very large inputs: prefer
+    /// `From<Vec<Vec<T>>>` otherwise. Without the feature, the
+    /// sub-arrays are copied on the current thread, as `From` does.
+    pub fn from_nested_parallel(value: Vec<Vec<T>>) -> Self {
+        #[cfg(not(feature = "parallel"))]
+        {
+            Self::from(value)
+        }
+        #[cfg(feature = "parallel")]
+        {
+            let mut indices = Vec::with_capacity(value.len() + 1);
+            indices.push(0);
+            for vec in &value {
+                indices.push(indices.last().unwrap() + vec.len());
+            }
+            let length = *indices.last().unwrap();
+            let mut content = Vec::with_capacity(length);
+            let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+            let batch_size = value.len().div_ceil(threads).max(1);
+            std::thread::scope(|scope| {
+                let mut region = &mut content.spare_capacity_mut()[..length];
+                let mut vecs = value.into_iter();
+                let mut start = 0;
+                while start < indices.len() - 1 {
+                    let end = (start + batch_size).min(indices.len() - 1);
+                    let batch: Vec<Vec<T>> = vecs.by_ref().take(end - start).collect();
+                    let (head, tail) = region.split_at_mut(indices[end] - indices[start]);
+                    region = tail;
+                    scope.spawn(move || {
+                        for (slot, s) in head.iter_mut().zip(batch.into_iter().flatten()) {
+                            slot.write(s);
+                        }
+                    });
+                    start = end;
+                }
+            });
+            // SAFETY: every slot in `0..length` has been written by exactly one
+            // thread, since the regions are disjoint and sized from `indices`.
+            unsafe { content.set_len(length) };
+            Self { content, indices }
+        }
+    }
 }
 
 impl<E, I> FromIterator<I> for FlatVec<E>
@@ -104,6 +410,28 @@ impl<T> From<Vec<Vec<T>>> for FlatVec<T> {
     }
 }
 
+impl<T: Clone> From<&[&[T]]> for FlatVec<T> {
+    /// Clones the sub-arrays into a content buffer allocated once, with
+    /// its exact size.
+    fn from(value: &[&[T]]) -> Self {
+        let length: usize = value.iter().map(|s| s.len()).sum();
+        let mut content = Vec::with_capacity(length);
+        let mut indices = Vec::with_capacity(value.len() + 1);
+        indices.push(0);
+        for slice in value {
+            content.extend_from_slice(slice);
+            indices.push(content.len());
+        }
+        Self { content, indices }
+    }
+}
+
+impl<T: Clone> From<Vec<&[T]>> for FlatVec<T> {
+    fn from(value: Vec<&[T]>) -> Self {
+        Self::from(value.as_slice())
+    }
+}
+
 impl<'a> From<Vec<Vec<&'a str>>> for FlatVec<Cow<'a, str>> {
     #[inline(always)]
     fn from(value: Vec<Vec<&'a str>>) -> Self {
@@ -210,4 +538,182 @@ mod test {
         };
         assert_eq!(expected, actual);
     }
+
+    #[test]
+    fn test_from_nested_parallel() {
+        let vecs: Vec<Vec<usize>> = (0..100).map(|i| (0..i % 7).collect()).collect();
+        let expected = FlatVec::new(vecs.clone());
+        let actual = FlatVec::from_nested_parallel(vecs);
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn test_iter_arrays_mut() {
+        let mut flat = FlatVec::new(vec![vec![1, 2, 3], vec![], vec![4, 5]]);
+        let arrays: Vec<&mut [i32]> = flat.iter_arrays_mut().collect();
+        assert_eq!(arrays.len(), 3);
+        for array in arrays {
+            array.iter_mut().for_each(|x| *x *= 10);
+        }
+        let expected = FlatVec::new(vec![vec![10, 20, 30], vec![], vec![40, 50]]);
+        assert_eq!(expected, flat);
+    }
+
+    #[test]
+    fn test_partition_by() {
+        let tokens = vec!["Hi", ".", "How", "are", "you", "?"];
+        let sentences = FlatVec::partition_by(tokens, |prev, _| *prev == "." || *prev == "?");
+        let expected = FlatVec::new(vec![vec!["Hi", "."], vec!["How", "are", "you", "?"]]);
+        assert_eq!(expected, sentences);
+        let empty = FlatVec::partition_by(Vec::<u8>::new(), |_, _| true);
+        assert_eq!(0, empty.iter_arrays().count());
+    }
+
+    #[test]
+    fn test_group_by_key() {
+        let pairs = vec![(1, "a"), (1, "b"), (2, "c"), (5, "d"), (5, "e")];
+        let (keys, groups) = FlatVec::group_by_key(pairs);
+        assert_eq!(vec![1, 2, 5], keys);
+        let expected = FlatVec::new(vec![vec!["a", "b"], vec!["c"], vec!["d", "e"]]);
+        assert_eq!(expected, groups);
+    }
+
+    #[test]
+    fn test_capacity() {
+        let mut flat_vec: FlatVec<u32> = FlatVec::with_capacity(10, 2);
+        assert!(flat_vec.capacity() >= 10);
+        assert!(flat_vec.arrays_capacity() >= 2);
+        flat_vec.reserve(100);
+        flat_vec.reserve_arrays(20);
+        assert!(flat_vec.capacity() >= 100);
+        assert!(flat_vec.arrays_capacity() >= 20);
+        flat_vec.shrink_to_fit();
+        assert_eq!(0, flat_vec.capacity());
+        assert_eq!(0, flat_vec.arrays_capacity());
+        assert_eq!(0, flat_vec.iter_arrays().count());
+        let raw: FlatVec<u32> = FlatVec::from_raw(vec![], vec![]);
+        assert_eq!(0, raw.arrays_capacity());
+    }
+
+    #[test]
+    fn test_empty_and_single() {
+        let empty: FlatVec<u32> = FlatVec::default();
+        assert_eq!(FlatVec::new_empty(), empty);
+        assert_eq!(vec![0], empty.indices);
+        assert_eq!(0, empty.iter_arrays().count());
+        let single = FlatVec::from_single([1, 2, 3]);
+        assert_eq!(vec![0, 3], single.indices);
+        assert_eq!(
+            vec![&[1, 2, 3][..]],
+            single.iter_arrays().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_into_parts() {
+        let flat_vec = FlatVec::new(vec![vec![1, 2], vec![3]]);
+        assert_eq!(vec![1, 2, 3], flat_vec.clone().into_content());
+        assert_eq!(vec![0, 2, 3], flat_vec.clone().into_indices());
+        let (content, indices) = flat_vec.into_parts();
+        assert_eq!(
+            FlatVec::from_raw(content, indices),
+            FlatVec::new(vec![vec![1, 2], vec![3]])
+        );
+    }
+
+    #[test]
+    fn test_from_slices() {
+        let slices: Vec<&[u32]> = vec![&[1, 2], &[], &[3]];
+        let flat_vec = FlatVec::from(slices.as_slice());
+        assert_eq!(vec![0, 2, 2, 3], flat_vec.indices);
+        assert_eq!(3, flat_vec.capacity());
+        assert_eq!(flat_vec, FlatVec::from(slices));
+    }
+
+    #[test]
+    fn test_mem_usage() {
+        let mut flat_vec: FlatVec<u32> = FlatVec::with_capacity(8, 3);
+        assert_eq!(8 * 4 + 4 * size_of::<usize>(), flat_vec.mem_usage());
+        flat_vec.shrink_to_fit();
+        assert_eq!(size_of::<usize>(), flat_vec.mem_usage());
+    }
+
+    #[test]
+    fn test_offset_type() {
+        let vecs = vec![vec![1u8, 2], vec![], vec![3]];
+        let mut flat_vec = FlatVec::<u8, u16>::try_from_nested(vecs.clone()).unwrap();
+        assert_eq!(vec![0u16, 2, 2, 3], flat_vec.indices);
+        flat_vec.iter_arrays_mut().for_each(|a| a.reverse());
+        assert_eq!(
+            vec![&[2, 1][..], &[], &[3]],
+            flat_vec.iter_arrays().collect::<Vec<_>>()
+        );
+        let flat_vec = flat_vec.into_usize_offsets();
+        assert_eq!(vec![0, 2, 2, 3], flat_vec.indices);
+        assert_eq!(
+            Err(FlatError::OffsetOverflow),
+            FlatVec::<u8, u16>::try_from_nested(vec![vec![0; 70_000]])
+        );
+    }
+
+    #[test]
+    fn test_dedup_within_arrays() {
+        let mut flat_vec = FlatVec::new(vec![
+            vec!["O", "O", "B", "I", "I", "O"],
+            vec![],
+            vec!["O", "O"],
+            vec!["B"],
+        ]);
+        flat_vec.dedup_within_arrays();
+        let expected = FlatVec::new(vec![vec!["O", "B", "I", "O"], vec![], vec!["O"], vec!["B"]]);
+        assert_eq!(expected, flat_vec);
+        let mut empty = FlatVec::<u8>::new_empty();
+        empty.dedup_within_arrays();
+        assert_eq!(FlatVec::new_empty(), empty);
+    }
+
+    #[test]
+    fn test_retain_elements() {
+        let mut flat_vec = FlatVec::new(vec![vec![1, 2, 3, 4], vec![5], vec![], vec![6, 7]]);
+        flat_vec.retain_elements(|i, &x| i == 2 || x % 2 == 0);
+        let expected = FlatVec::new(vec![vec![2, 4], vec![], vec![], vec![6]]);
+        assert_eq!(expected, flat_vec);
+        flat_vec.retain_elements(|i, _| i != 0);
+        assert_eq!(
+            FlatVec::new(vec![vec![], vec![], vec![], vec![6]]),
+            flat_vec
+        );
+    }
+
+    #[test]
+    fn test_split_array_at() {
+        let mut flat_vec = FlatVec::new(vec![vec![1, 2, 3], vec![4, 5]]);
+        flat_vec.split_array_at(0, 1);
+        flat_vec.split_array_at(2, 2);
+        assert_eq!(vec![0, 1, 3, 5, 5], flat_vec.indices);
+        assert_eq!(
+            FlatVec::new(vec![vec![1], vec![2, 3], vec![4, 5], vec![]]),
+            flat_vec
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "split position 3 out of bounds for array of length 2")]
+    fn test_split_array_at_out_of_bounds() {
+        let mut flat_vec = FlatVec::new(vec![vec![1, 2, 3], vec![4, 5]]);
+        flat_vec.split_array_at(1, 3);
+    }
+
+    #[test]
+    fn test_replace_array() {
+        let mut flat_vec = FlatVec::new(vec![vec![1, 2], vec![3, 4, 5], vec![6]]);
+        flat_vec.replace_array(1, [7]);
+        assert_eq!(FlatVec::new(vec![vec![1, 2], vec![7], vec![6]]), flat_vec);
+        flat_vec.replace_array(0, vec![8, 9, 10, 11]);
+        flat_vec.replace_array(2, []);
+        assert_eq!(
+            FlatVec::new(vec![vec![8, 9, 10, 11], vec![7], vec![]]),
+            flat_vec
+        );
+    }
 }
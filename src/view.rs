@@ -0,0 +1,155 @@
+use crate::iterator::{FlattenedCollection, Iter, get_offset, get_range, indices_are_valid};
+use crate::{FlatArray, FlatVec};
+use std::ops::Deref;
+
+/// Borrowed flat collection, backed by a content slice and an offsets
+/// slice owned by someone else (e.g. a memory map, an Arrow buffer or
+/// a C caller). Nothing is copied when building it.
+#[derive(Debug, Eq, PartialEq, PartialOrd, Ord, Hash)]
+pub struct FlatView<'a, T> {
+    pub(crate) content: &'a [T],
+    pub(crate) indices: &'a [usize],
+}
+
+impl<T> Clone for FlatView<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for FlatView<'_, T> {}
+
+impl<T> FlattenedCollection<T> for FlatView<'_, T> {
+    fn indices_len(&self) -> usize {
+        self.indices.len()
+    }
+    fn content_len(&self) -> usize {
+        self.content.len()
+    }
+    unsafe fn get_indices(&self, index: usize) -> usize {
+        unsafe { get_offset(self.indices, index) }
+    }
+    unsafe fn get_content(&self, range: std::ops::Range<usize>) -> &[T] {
+        unsafe { get_range(self.content, range) }
+    }
+}
+
+impl<'a, T> FlatView<'a, T> {
+    /// Builds a view over `content`, split according to `indices`.
+    /// Returns `None` if the offsets decrease or point past the end of
+    /// the content.
+    pub fn new(content: &'a [T], indices: &'a [usize]) -> Option<Self> {
+        if indices_are_valid(indices, content.len()) {
+            Some(Self { content, indices })
+        } else {
+            None
+        }
+    }
+    /// Builds a view without checking the offsets.
+    ///
+    /// # Safety
+    /// The offsets must never decrease and must all be smaller than or
+    /// equal to `content.len()`.
+    pub unsafe fn new_unchecked(content: &'a [T], indices: &'a [usize]) -> Self {
+        Self { content, indices }
+    }
+    /// Borrows the content of the view as a slice. Only the elements
+    /// covered by the offsets, from the first one to the last one, are
+    /// part of the view.
+    pub fn get_content(&self) -> &'a [T] {
+        match (self.indices.first(), self.indices.last()) {
+            (Some(&first), Some(&last)) => &self.content[first..last],
+            _ => &[],
+        }
+    }
+    /// Returns an iterator over the content. This iterator returns the
+    /// individual elements.
+    pub fn iter(&self) -> std::slice::Iter<'a, T> {
+        self.get_content().iter()
+    }
+    /// Returns an iterator over the arrays of the view. The iterator
+    /// will return a slice of type `&[T]`.
+    pub fn iter_arrays(&self) -> Iter<'_, FlatView<'a, T>, T> {
+        Iter::new(self)
+    }
+}
+
+impl<T> Deref for FlatView<'_, T> {
+    type Target = [T];
+    fn deref(&self) -> &Self::Target {
+        self.get_content()
+    }
+}
+
+impl<'a, T> From<&'a FlatVec<T>> for FlatView<'a, T> {
+    fn from(value: &'a FlatVec<T>) -> Self {
+        Self {
+            content: &value.content,
+            indices: &value.indices,
+        }
+    }
+}
+
+impl<'a, T> From<&'a FlatArray<T>> for FlatView<'a, T> {
+    fn from(value: &'a FlatArray<T>) -> Self {
+        Self {
+            content: &value.content,
+            indices: &value.indices,
+        }
+    }
+}
+
+impl<T> FlatArray<T> {
+    /// Consumes and leaks the `FlatArray`, returning a view over its
+    /// buffers that lives for the rest of the program, e.g. for a lookup
+    /// table loaded once at startup. See `Box::leak`.
+    pub fn leak<'a>(self) -> FlatView<'a, T> {
+        FlatView {
+            content: Box::leak(self.content),
+            indices: Box::leak(self.indices),
+        }
+    }
+}
+
+impl<T> FlatVec<T> {
+    /// Consumes and leaks the `FlatVec`, returning a view over its
+    /// buffers that lives for the rest of the program. See `Vec::leak`.
+    pub fn leak<'a>(self) -> FlatView<'a, T> {
+        FlatView {
+            content: self.content.leak(),
+            indices: self.indices.leak(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_iter_view() {
+        let content = [1, 2, 3, 4, 5];
+        let indices = [0, 2, 2, 5];
+        let view = FlatView::new(&content, &indices).unwrap();
+        let actual: Vec<_> = view.iter_arrays().collect();
+        let expected: Vec<&[i32]> = vec![&[1, 2], &[], &[3, 4, 5]];
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn test_invalid_view() {
+        let content = [1, 2, 3];
+        assert!(FlatView::new(&content, &[0, 2, 1]).is_none());
+        assert!(FlatView::new(&content, &[0, 4]).is_none());
+    }
+
+    #[test]
+    fn test_leak() {
+        let table: FlatView<'static, &str> =
+            FlatArray::new(vec![vec!["O"], vec!["B-PER", "I-PER"]]).leak();
+        let arrays: Vec<_> = table.iter_arrays().collect();
+        assert_eq!(vec![&["O"][..], &["B-PER", "I-PER"]], arrays);
+        let view: FlatView<'static, u8> = FlatVec::new(vec![vec![1, 2]]).leak();
+        assert_eq!(&[1, 2], view.get_content());
+    }
+}